@@ -0,0 +1,126 @@
+use crate::Error;
+use niri_ipc::Reply;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::time::SystemTime;
+
+/// One action sent through a `Niri`, as kept by its action log.
+#[derive(Debug, Clone, Serialize)]
+pub struct LoggedAction {
+    pub at: SystemTime,
+    pub action: serde_json::Value,
+    pub status: ActionStatus,
+}
+
+/// What became of a logged action.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub enum ActionStatus {
+    Handled,
+    /// niri replied with this error.
+    Rejected(String),
+    /// Printed instead of sent, in dry-run mode.
+    DryRun,
+    /// niri couldn't be reached, or its reply couldn't be read.
+    Failed(String),
+}
+
+impl ActionStatus {
+    fn of(result: &Result<serde_json::Value, Error>) -> Self {
+        let reply = match result {
+            Ok(serde_json::Value::Null) => return ActionStatus::DryRun,
+            Ok(reply) => reply,
+            Err(e) => return ActionStatus::Failed(e.to_string()),
+        };
+        match serde_json::from_value::<Reply>(reply.clone()) {
+            Ok(Ok(_)) => ActionStatus::Handled,
+            Ok(Err(msg)) => ActionStatus::Rejected(msg),
+            Err(e) => ActionStatus::Failed(e.to_string()),
+        }
+    }
+}
+
+/// The actions a `Niri` has sent, trimmed to the most recent `capacity`
+/// when that is set.
+#[derive(Debug)]
+pub(crate) struct ActionLog {
+    entries: VecDeque<LoggedAction>,
+    capacity: Option<usize>,
+}
+
+impl ActionLog {
+    pub(crate) fn new(capacity: Option<usize>) -> Self {
+        ActionLog {
+            entries: VecDeque::new(),
+            capacity,
+        }
+    }
+
+    pub(crate) fn push(
+        &mut self,
+        action: serde_json::Value,
+        result: &Result<serde_json::Value, Error>,
+    ) {
+        if let Some(capacity) = self.capacity {
+            if capacity == 0 {
+                return;
+            }
+            if self.entries.len() >= capacity {
+                self.entries.pop_front();
+            }
+        }
+        self.entries.push_back(LoggedAction {
+            at: SystemTime::now(),
+            action,
+            status: ActionStatus::of(result),
+        });
+    }
+
+    pub(crate) fn entries(&self) -> impl Iterator<Item = &LoggedAction> {
+        self.entries.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn logged(log: &ActionLog) -> Vec<serde_json::Value> {
+        log.entries().map(|e| e.action.clone()).collect()
+    }
+
+    #[test]
+    fn zero_capacity_keeps_nothing() {
+        let mut log = ActionLog::new(Some(0));
+        log.push(json!(1), &Ok(serde_json::Value::Null));
+        assert!(logged(&log).is_empty());
+    }
+
+    #[test]
+    fn oldest_entries_are_evicted_first() {
+        let mut log = ActionLog::new(Some(2));
+        for n in 1..=3 {
+            log.push(json!(n), &Ok(serde_json::Value::Null));
+        }
+        assert_eq!(logged(&log), [json!(2), json!(3)]);
+    }
+
+    #[test]
+    fn statuses_follow_the_reply() {
+        let mut log = ActionLog::new(None);
+        log.push(json!(1), &Ok(serde_json::Value::Null));
+        log.push(json!(2), &Ok(json!({ "Ok": "Handled" })));
+        log.push(json!(3), &Ok(json!({ "Err": "no such window" })));
+        log.push(json!(4), &Err(Error::Disconnected));
+        let statuses: Vec<ActionStatus> = log.entries().map(|e| e.status.clone()).collect();
+        assert_eq!(
+            statuses,
+            [
+                ActionStatus::DryRun,
+                ActionStatus::Handled,
+                ActionStatus::Rejected("no such window".into()),
+                ActionStatus::Failed(Error::Disconnected.to_string()),
+            ]
+        );
+    }
+}
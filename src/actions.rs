@@ -0,0 +1,818 @@
+use crate::ipc::create_parent_dir;
+use crate::parse::{parse_size_change, set_proportion};
+use crate::shell;
+use niri_ipc::{
+    ColumnDisplay, LayoutSwitchTarget, PositionChange, SizeChange, WorkspaceReferenceArg,
+};
+use serde_json::json;
+
+/// Everything niri can be told to do without reading anything back.
+///
+/// `Niri` and `CommandHandle` send these over niri's socket; other
+/// implementors can record, log or delay them instead. Only `send_action` has
+/// to be implemented. Code that takes `&mut impl NiriActions` can chain calls
+/// on the reference without giving up ownership.
+pub trait NiriActions: Sized {
+    /// Sends one action in niri's JSON form, e.g. `{"FocusColumnLeft": {}}`.
+    fn send_action(&mut self, action: serde_json::Value);
+
+    // -------------------------------------------------------------------------
+    //  Spawning & Session
+    // -------------------------------------------------------------------------
+
+    fn spawn_args(mut self, cmd: Vec<String>) -> Self {
+        self.send_action(json!({ "Spawn": { "command": cmd } }));
+        self
+    }
+
+    fn sh(mut self, cmd: &str) -> Self {
+        self.send_action(json!({ "SpawnSh": { "command": cmd } }));
+        self
+    }
+
+    /// Runs `program` with `args` through the shell, quoting each of them so
+    /// spaces, quotes and other shell syntax reach the program literally.
+    /// Use `sh` when the whole command line is meant to be shell syntax.
+    fn sh_fmt(self, program: &str, args: &[&str]) -> Self {
+        let line: Vec<String> = std::iter::once(program)
+            .chain(args.iter().copied())
+            .map(shell::quote)
+            .collect();
+        self.sh(&line.join(" "))
+    }
+
+    /// Like `spawn_args`, with extra environment variables for the process.
+    /// niri's `Spawn` has no way to pass them, so this runs `env` through
+    /// `sh` with every variable and argument shell-quoted.
+    fn spawn_env(self, cmd: Vec<String>, env: Vec<(String, String)>) -> Self {
+        let vars = env.iter().map(|(k, v)| shell::quote(&format!("{k}={v}")));
+        let args = cmd.iter().map(|arg| shell::quote(arg));
+        let line: Vec<String> = std::iter::once("env".to_string())
+            .chain(vars)
+            .chain(args)
+            .collect();
+        self.sh(&line.join(" "))
+    }
+
+    fn quit(mut self, skip_confirm: bool) -> Self {
+        self.send_action(json!({ "Quit": { "skip_confirmation": skip_confirm } }));
+        self
+    }
+    fn quit_forced(self) -> Self {
+        self.quit(true)
+    }
+    fn quit_interactive(self) -> Self {
+        self.quit(false)
+    }
+
+    fn reload_config(mut self) -> Self {
+        self.send_action(json!({ "LoadConfigFile": {} }));
+        self
+    }
+
+    /// Would ask niri to load the config at `path` instead of the default
+    /// location, but no released niri can; 25.11, the version this crate is
+    /// built against, was checked. `LoadConfigFile` has no `path` field and
+    /// niri ignores unknown ones, so sending it would quietly reload the
+    /// default config. This panics instead. Switching configs needs the file
+    /// at the default location swapped.
+    fn reload_config_from(self, path: &str) -> Self {
+        panic!("niri can only reload its default config, not {path}");
+    }
+
+    // -------------------------------------------------------------------------
+    //  Focus Actions
+    // -------------------------------------------------------------------------
+
+    // Basic Directional
+    fn foc_l(mut self) -> Self {
+        self.send_action(json!({ "FocusColumnLeft": {} }));
+        self
+    }
+    fn foc_r(mut self) -> Self {
+        self.send_action(json!({ "FocusColumnRight": {} }));
+        self
+    }
+    fn foc_u(mut self) -> Self {
+        self.send_action(json!({ "FocusWindowUp": {} }));
+        self
+    }
+    fn foc_d(mut self) -> Self {
+        self.send_action(json!({ "FocusWindowDown": {} }));
+        self
+    }
+
+    // Absolute / Specific
+    fn foc_id(mut self, id: u64) -> Self {
+        self.send_action(json!({ "FocusWindow": { "id": id } }));
+        self
+    }
+    fn foc_idx(mut self, idx: u8) -> Self {
+        self.send_action(json!({ "FocusWindowInColumn": { "index": idx } }));
+        self
+    }
+    fn foc_prev(mut self) -> Self {
+        self.send_action(json!({ "FocusWindowPrevious": {} }));
+        self
+    }
+    fn foc_top(mut self) -> Self {
+        self.send_action(json!({ "FocusWindowTop": {} }));
+        self
+    }
+    fn foc_bottom(mut self) -> Self {
+        self.send_action(json!({ "FocusWindowBottom": {} }));
+        self
+    }
+
+    // Column Traversal
+    fn foc_col_idx(mut self, idx: usize) -> Self {
+        self.send_action(json!({ "FocusColumn": { "index": idx } }));
+        self
+    }
+    fn foc_col_first(mut self) -> Self {
+        self.send_action(json!({ "FocusColumnFirst": {} }));
+        self
+    }
+    fn foc_col_last(mut self) -> Self {
+        self.send_action(json!({ "FocusColumnLast": {} }));
+        self
+    }
+    fn foc_col_next_loop(mut self) -> Self {
+        self.send_action(json!({ "FocusColumnRightOrFirst": {} }));
+        self
+    }
+    fn foc_col_prev_loop(mut self) -> Self {
+        self.send_action(json!({ "FocusColumnLeftOrLast": {} }));
+        self
+    }
+
+    // Smart Focus (Combinations)
+    fn foc_win_mon_u(mut self) -> Self {
+        self.send_action(json!({ "FocusWindowOrMonitorUp": {} }));
+        self
+    }
+    fn foc_win_mon_d(mut self) -> Self {
+        self.send_action(json!({ "FocusWindowOrMonitorDown": {} }));
+        self
+    }
+    fn foc_col_mon_l(mut self) -> Self {
+        self.send_action(json!({ "FocusColumnOrMonitorLeft": {} }));
+        self
+    }
+    fn foc_col_mon_r(mut self) -> Self {
+        self.send_action(json!({ "FocusColumnOrMonitorRight": {} }));
+        self
+    }
+    fn foc_d_col_l(mut self) -> Self {
+        self.send_action(json!({ "FocusWindowDownOrColumnLeft": {} }));
+        self
+    }
+    fn foc_d_col_r(mut self) -> Self {
+        self.send_action(json!({ "FocusWindowDownOrColumnRight": {} }));
+        self
+    }
+    fn foc_u_col_l(mut self) -> Self {
+        self.send_action(json!({ "FocusWindowUpOrColumnLeft": {} }));
+        self
+    }
+    fn foc_u_col_r(mut self) -> Self {
+        self.send_action(json!({ "FocusWindowUpOrColumnRight": {} }));
+        self
+    }
+    fn foc_wspace_d(mut self) -> Self {
+        self.send_action(json!({ "FocusWindowOrWorkspaceDown": {} }));
+        self
+    }
+    fn foc_wspace_u(mut self) -> Self {
+        self.send_action(json!({ "FocusWindowOrWorkspaceUp": {} }));
+        self
+    }
+
+    // -------------------------------------------------------------------------
+    //  Window & Column Movement
+    // -------------------------------------------------------------------------
+
+    // Window Movement
+    fn mv_win_u(mut self) -> Self {
+        self.send_action(json!({ "MoveWindowUp": {} }));
+        self
+    }
+    fn mv_win_d(mut self) -> Self {
+        self.send_action(json!({ "MoveWindowDown": {} }));
+        self
+    }
+    fn mv_win_u_wspace(mut self) -> Self {
+        self.send_action(json!({ "MoveWindowUpOrToWorkspaceUp": {} }));
+        self
+    }
+    fn mv_win_d_wspace(mut self) -> Self {
+        self.send_action(json!({ "MoveWindowDownOrToWorkspaceDown": {} }));
+        self
+    }
+
+    // Column Movement
+    fn mv_col_l(mut self) -> Self {
+        self.send_action(json!({ "MoveColumnLeft": {} }));
+        self
+    }
+    fn mv_col_r(mut self) -> Self {
+        self.send_action(json!({ "MoveColumnRight": {} }));
+        self
+    }
+    fn mv_col_first(mut self) -> Self {
+        self.send_action(json!({ "MoveColumnToFirst": {} }));
+        self
+    }
+    fn mv_col_last(mut self) -> Self {
+        self.send_action(json!({ "MoveColumnToLast": {} }));
+        self
+    }
+    fn mv_col_idx(mut self, idx: usize) -> Self {
+        self.send_action(json!({ "MoveColumnToIndex": { "index": idx } }));
+        self
+    }
+    fn mv_col_l_mon(mut self) -> Self {
+        self.send_action(json!({ "MoveColumnLeftOrToMonitorLeft": {} }));
+        self
+    }
+    fn mv_col_r_mon(mut self) -> Self {
+        self.send_action(json!({ "MoveColumnRightOrToMonitorRight": {} }));
+        self
+    }
+
+    // -------------------------------------------------------------------------
+    //  Layout Manipulation & Sizing
+    // -------------------------------------------------------------------------
+
+    // Consumption / Expulsion / Swapping
+    fn consume(mut self) -> Self {
+        self.send_action(json!({ "ConsumeWindowIntoColumn": {} }));
+        self
+    }
+    /// Consumes the next `n` windows to the right into the focused column.
+    fn consume_n(mut self, n: usize) -> Self {
+        for _ in 0..n {
+            self = self.consume();
+        }
+        self
+    }
+    fn consume_expel_l(mut self, id: Option<u64>) -> Self {
+        self.send_action(json!({ "ConsumeOrExpelWindowLeft": { "id": id } }));
+        self
+    }
+    fn consume_expel_r(mut self, id: Option<u64>) -> Self {
+        self.send_action(json!({ "ConsumeOrExpelWindowRight": { "id": id } }));
+        self
+    }
+    fn expel(mut self) -> Self {
+        self.send_action(json!({ "ExpelWindowFromColumn": {} }));
+        self
+    }
+    fn swap_l(mut self) -> Self {
+        self.send_action(json!({ "SwapWindowLeft": {} }));
+        self
+    }
+    fn swap_r(mut self) -> Self {
+        self.send_action(json!({ "SwapWindowRight": {} }));
+        self
+    }
+
+    // Layout Display
+    fn layout_switch(mut self, target: LayoutSwitchTarget) -> Self {
+        self.send_action(json!({ "SwitchLayout": { "layout": target } }));
+        self
+    }
+    fn toggle_tab(mut self) -> Self {
+        self.send_action(json!({ "ToggleColumnTabbedDisplay": {} }));
+        self
+    }
+    fn col_display(mut self, mode: ColumnDisplay) -> Self {
+        self.send_action(json!({ "SetColumnDisplay": { "display": mode } }));
+        self
+    }
+
+    // Centering
+    fn center_col(mut self) -> Self {
+        self.send_action(json!({ "CenterColumn": {} }));
+        self
+    }
+    fn center_win(mut self, id: Option<u64>) -> Self {
+        self.send_action(json!({ "CenterWindow": { "id": id } }));
+        self
+    }
+    fn center_vis_cols(mut self) -> Self {
+        self.send_action(json!({ "CenterVisibleColumns": {} }));
+        self
+    }
+
+    // Dimensions (Columns)
+    /// Sets the column width as a fraction of the output, e.g. `0.5` for
+    /// half. Values outside `(0.0, 1.0]` are clamped into range with a warning.
+    fn col_width(self, val: f64) -> Self {
+        self.col_width_change(set_proportion(val))
+    }
+    fn col_width_change(mut self, c: SizeChange) -> Self {
+        self.send_action(json!({ "SetColumnWidth": { "change": c } }));
+        self
+    }
+    /// Like `col_width_change`, taking a size the way `niri msg` does, e.g.
+    /// `"800"`, `"+100"` or `"50%"`. Panics if `s` doesn't parse.
+    fn col_width_str(self, s: &str) -> Self {
+        let change = parse_size_change(s).unwrap_or_else(|e| panic!("{e}"));
+        self.col_width_change(change)
+    }
+    fn col_max(mut self) -> Self {
+        self.send_action(json!({ "MaximizeColumn": {} }));
+        self
+    }
+    fn expand_col(mut self) -> Self {
+        self.send_action(json!({ "ExpandColumnToAvailableWidth": {} }));
+        self
+    }
+    fn preset_col_width(mut self) -> Self {
+        self.send_action(json!({ "SwitchPresetColumnWidth": {} }));
+        self
+    }
+    fn preset_col_width_back(mut self) -> Self {
+        self.send_action(json!({ "SwitchPresetColumnWidthBack": {} }));
+        self
+    }
+    /// Jumps to preset column width `idx`, counted in config order.
+    ///
+    /// niri can only cycle presets, so the column is first set to a fixed 1px
+    /// width. That clears niri's preset position and makes the next switch
+    /// land on the first preset; from there it is cycled forward `idx` times.
+    fn col_width_preset(mut self, idx: usize) -> Self {
+        self.send_action(json!({ "SetColumnWidth": { "change": { "SetFixed": 1 } } }));
+        for _ in 0..=idx {
+            self = self.preset_col_width();
+        }
+        self
+    }
+
+    // Dimensions (Windows)
+    fn win_width(mut self, id: Option<u64>, c: SizeChange) -> Self {
+        self.send_action(json!({ "SetWindowWidth": { "id": id, "change": c } }));
+        self
+    }
+    /// Like `col_width_str`, for a single window.
+    fn win_width_str(self, id: Option<u64>, s: &str) -> Self {
+        let change = parse_size_change(s).unwrap_or_else(|e| panic!("{e}"));
+        self.win_width(id, change)
+    }
+    /// Like `col_width`, for a single window.
+    fn win_width_prop(self, id: Option<u64>, val: f64) -> Self {
+        self.win_width(id, set_proportion(val))
+    }
+    fn win_width_px(self, id: Option<u64>, px: i32) -> Self {
+        self.win_width(id, SizeChange::SetFixed(px))
+    }
+    /// Sets the focused window's height as a fraction of the output. Values
+    /// outside `(0.0, 1.0]` are clamped into range with a warning.
+    fn win_height(mut self, val: f64) -> Self {
+        self.send_action(json!({ "SetWindowHeight": { "change": set_proportion(val) } }));
+        self
+    }
+    fn win_height_px(mut self, id: Option<u64>, px: i32) -> Self {
+        self.send_action(json!({
+            "SetWindowHeight": { "id": id, "change": SizeChange::SetFixed(px) }
+        }));
+        self
+    }
+    fn reset_win_height(mut self, id: Option<u64>) -> Self {
+        self.send_action(json!({ "ResetWindowHeight": { "id": id } }));
+        self
+    }
+    fn max_win_edge(mut self, id: Option<u64>) -> Self {
+        self.send_action(json!({ "MaximizeWindowToEdges": { "id": id } }));
+        self
+    }
+    fn preset_win_width(mut self, id: Option<u64>) -> Self {
+        self.send_action(json!({ "SwitchPresetWindowWidth": { "id": id } }));
+        self
+    }
+    fn preset_win_width_back(mut self, id: Option<u64>) -> Self {
+        self.send_action(json!({ "SwitchPresetWindowWidthBack": { "id": id } }));
+        self
+    }
+    /// Jumps window `id` (`None` for the focused one) to preset width `idx`,
+    /// the same way `col_width_preset` does for columns.
+    fn win_width_preset(mut self, id: Option<u64>, idx: usize) -> Self {
+        self = self.win_width_px(id, 1);
+        for _ in 0..=idx {
+            self = self.preset_win_width(id);
+        }
+        self
+    }
+    fn preset_win_height(mut self, id: Option<u64>) -> Self {
+        self.send_action(json!({ "SwitchPresetWindowHeight": { "id": id } }));
+        self
+    }
+    fn preset_win_height_back(mut self, id: Option<u64>) -> Self {
+        self.send_action(json!({ "SwitchPresetWindowHeightBack": { "id": id } }));
+        self
+    }
+
+    // -------------------------------------------------------------------------
+    //  Window State (Close, Fullscreen, Float, Urgent)
+    // -------------------------------------------------------------------------
+
+    fn close(mut self, id: Option<u64>) -> Self {
+        self.send_action(json!({ "CloseWindow": { "id": id } }));
+        self
+    }
+
+    fn fullscreen(mut self, id: Option<u64>) -> Self {
+        self.send_action(json!({ "FullscreenWindow": { "id": id } }));
+        self
+    }
+
+    fn fake_fullscreen(mut self, id: Option<u64>) -> Self {
+        self.send_action(json!({ "ToggleWindowedFullscreen": { "id": id } }));
+        self
+    }
+
+    /// Toggles the opacity set by the window's rules on and off. niri has no
+    /// action to set an arbitrary opacity; use a window rule for that.
+    fn opacity_toggle(mut self, id: Option<u64>) -> Self {
+        self.send_action(json!({ "ToggleWindowRuleOpacity": { "id": id } }));
+        self
+    }
+
+    // Floating / Tiling
+    fn float_toggle(mut self, id: Option<u64>) -> Self {
+        self.send_action(json!({ "ToggleWindowFloating": { "id": id } }));
+        self
+    }
+    fn mv_float(mut self, id: Option<u64>) -> Self {
+        self.send_action(json!({ "MoveWindowToFloating": { "id": id } }));
+        self
+    }
+    fn mv_tile(mut self, id: Option<u64>) -> Self {
+        self.send_action(json!({ "MoveWindowToTiling": { "id": id } }));
+        self
+    }
+    fn foc_float(mut self) -> Self {
+        self.send_action(json!({ "FocusFloating": {} }));
+        self
+    }
+    fn foc_tile(mut self) -> Self {
+        self.send_action(json!({ "FocusTiling": {} }));
+        self
+    }
+    fn foc_float_tile_switch(mut self) -> Self {
+        self.send_action(json!({ "SwitchFocusBetweenFloatingAndTiling": {} }));
+        self
+    }
+    fn mv_float_win(mut self, id: Option<u64>, x: PositionChange, y: PositionChange) -> Self {
+        self.send_action(json!({ "MoveFloatingWindow": { "id": id, "x": x, "y": y } }));
+        self
+    }
+
+    // Urgency
+    fn urgent_toggle(mut self, id: u64) -> Self {
+        self.send_action(json!({ "ToggleWindowUrgent": { "id": id } }));
+        self
+    }
+    fn urgent_set(mut self, id: u64) -> Self {
+        self.send_action(json!({ "SetWindowUrgent": { "id": id } }));
+        self
+    }
+    fn urgent_unset(mut self, id: u64) -> Self {
+        self.send_action(json!({ "UnsetWindowUrgent": { "id": id } }));
+        self
+    }
+
+    // -------------------------------------------------------------------------
+    //  Workspace Management
+    // -------------------------------------------------------------------------
+
+    // Workspace Focus/Switch
+    fn foc_wspace(mut self, r: WorkspaceReferenceArg) -> Self {
+        self.send_action(json!({ "FocusWorkspace": { "reference": r } }));
+        self
+    }
+    fn foc_wspace_name(self, name: &str) -> Self {
+        self.foc_wspace(WorkspaceReferenceArg::Name(name.to_string()))
+    }
+    fn foc_wspace_prev(mut self) -> Self {
+        self.send_action(json!({ "FocusWorkspacePrevious": {} }));
+        self
+    }
+    fn wspace_d(mut self) -> Self {
+        self.send_action(json!({ "FocusWorkspaceDown": {} }));
+        self
+    }
+    fn wspace_u(mut self) -> Self {
+        self.send_action(json!({ "FocusWorkspaceUp": {} }));
+        self
+    }
+
+    // Workspace Movement (Reordering)
+    fn mv_wspace_d(mut self) -> Self {
+        self.send_action(json!({ "MoveWorkspaceDown": {} }));
+        self
+    }
+    fn mv_wspace_u(mut self) -> Self {
+        self.send_action(json!({ "MoveWorkspaceUp": {} }));
+        self
+    }
+    fn mv_wspace_idx(mut self, idx: usize, r: Option<WorkspaceReferenceArg>) -> Self {
+        self.send_action(json!({ "MoveWorkspaceToIndex": { "index": idx, "reference": r } }));
+        self
+    }
+    fn mv_current_wspace_idx(self, idx: usize) -> Self {
+        self.mv_wspace_idx(idx, None)
+    }
+    fn mv_wspace_to_index_named(self, name: &str, idx: usize) -> Self {
+        self.mv_wspace_idx(idx, Some(WorkspaceReferenceArg::Name(name.to_string())))
+    }
+
+    // Moving Content to Workspaces
+    fn mv_win_wspace(mut self, id: Option<u64>, r: WorkspaceReferenceArg, focus: bool) -> Self {
+        self.send_action(json!({
+            "MoveWindowToWorkspace": { "window_id": id, "reference": r, "focus": focus }
+        }));
+        self
+    }
+    fn mv_win_wspace_name(self, id: Option<u64>, name: &str, focus: bool) -> Self {
+        self.mv_win_wspace(id, WorkspaceReferenceArg::Name(name.to_string()), focus)
+    }
+    fn mv_win_wspace_index(self, id: Option<u64>, idx: u8, focus: bool) -> Self {
+        self.mv_win_wspace(id, WorkspaceReferenceArg::Index(idx), focus)
+    }
+    fn mv_win_wspace_d(mut self, focus: bool) -> Self {
+        self.send_action(json!({ "MoveWindowToWorkspaceDown": { "focus": focus } }));
+        self
+    }
+    fn mv_win_wspace_u(mut self, focus: bool) -> Self {
+        self.send_action(json!({ "MoveWindowToWorkspaceUp": { "focus": focus } }));
+        self
+    }
+    fn mv_col_wspace(mut self, r: WorkspaceReferenceArg, focus: bool) -> Self {
+        self.send_action(json!({ "MoveColumnToWorkspace": { "reference": r, "focus": focus } }));
+        self
+    }
+    fn mv_col_wspace_name(self, name: &str, focus: bool) -> Self {
+        self.mv_col_wspace(WorkspaceReferenceArg::Name(name.to_string()), focus)
+    }
+    fn mv_col_wspace_index(self, idx: u8, focus: bool) -> Self {
+        self.mv_col_wspace(WorkspaceReferenceArg::Index(idx), focus)
+    }
+    fn mv_col_wspace_d(mut self, focus: bool) -> Self {
+        self.send_action(json!({ "MoveColumnToWorkspaceDown": { "focus": focus } }));
+        self
+    }
+    fn mv_col_wspace_u(mut self, focus: bool) -> Self {
+        self.send_action(json!({ "MoveColumnToWorkspaceUp": { "focus": focus } }));
+        self
+    }
+
+    // Naming
+    fn name_wspace(mut self, name: String, r: Option<WorkspaceReferenceArg>) -> Self {
+        self.send_action(json!({ "SetWorkspaceName": { "name": name, "workspace": r } }));
+        self
+    }
+    fn unname_wspace(mut self, r: Option<WorkspaceReferenceArg>) -> Self {
+        self.send_action(json!({ "UnsetWorkspaceName": { "reference": r } }));
+        self
+    }
+
+    // -------------------------------------------------------------------------
+    //  Monitor Management
+    // -------------------------------------------------------------------------
+
+    // Focus
+    fn monitor_l(mut self) -> Self {
+        self.send_action(json!({ "FocusMonitorLeft": {} }));
+        self
+    }
+    fn monitor_r(mut self) -> Self {
+        self.send_action(json!({ "FocusMonitorRight": {} }));
+        self
+    }
+    fn monitor_u(mut self) -> Self {
+        self.send_action(json!({ "FocusMonitorUp": {} }));
+        self
+    }
+    fn monitor_d(mut self) -> Self {
+        self.send_action(json!({ "FocusMonitorDown": {} }));
+        self
+    }
+    fn monitor_prev(mut self) -> Self {
+        self.send_action(json!({ "FocusMonitorPrevious": {} }));
+        self
+    }
+    fn monitor_next(mut self) -> Self {
+        self.send_action(json!({ "FocusMonitorNext": {} }));
+        self
+    }
+    fn monitor_name(mut self, out: String) -> Self {
+        self.send_action(json!({ "FocusMonitor": { "output": out } }));
+        self
+    }
+
+    // Move Window to Monitor
+    fn mv_win_mon(mut self, id: Option<u64>, out: String) -> Self {
+        self.send_action(json!({ "MoveWindowToMonitor": { "id": id, "output": out } }));
+        self
+    }
+    fn mv_win_mon_l(mut self) -> Self {
+        self.send_action(json!({ "MoveWindowToMonitorLeft": {} }));
+        self
+    }
+    fn mv_win_mon_r(mut self) -> Self {
+        self.send_action(json!({ "MoveWindowToMonitorRight": {} }));
+        self
+    }
+    fn mv_win_mon_u(mut self) -> Self {
+        self.send_action(json!({ "MoveWindowToMonitorUp": {} }));
+        self
+    }
+    fn mv_win_mon_d(mut self) -> Self {
+        self.send_action(json!({ "MoveWindowToMonitorDown": {} }));
+        self
+    }
+    fn mv_win_mon_prev(mut self) -> Self {
+        self.send_action(json!({ "MoveWindowToMonitorPrevious": {} }));
+        self
+    }
+    fn mv_win_mon_next(mut self) -> Self {
+        self.send_action(json!({ "MoveWindowToMonitorNext": {} }));
+        self
+    }
+
+    // Move Column to Monitor
+    fn mv_col_mon(mut self, out: String) -> Self {
+        self.send_action(json!({ "MoveColumnToMonitor": { "output": out } }));
+        self
+    }
+    fn mv_col_mon_l(mut self) -> Self {
+        self.send_action(json!({ "MoveColumnToMonitorLeft": {} }));
+        self
+    }
+    fn mv_col_mon_r(mut self) -> Self {
+        self.send_action(json!({ "MoveColumnToMonitorRight": {} }));
+        self
+    }
+    fn mv_col_mon_u(mut self) -> Self {
+        self.send_action(json!({ "MoveColumnToMonitorUp": {} }));
+        self
+    }
+    fn mv_col_mon_d(mut self) -> Self {
+        self.send_action(json!({ "MoveColumnToMonitorDown": {} }));
+        self
+    }
+    fn mv_col_mon_prev(mut self) -> Self {
+        self.send_action(json!({ "MoveColumnToMonitorPrevious": {} }));
+        self
+    }
+    fn mv_col_mon_next(mut self) -> Self {
+        self.send_action(json!({ "MoveColumnToMonitorNext": {} }));
+        self
+    }
+
+    // Move Workspace to Monitor
+    fn mv_wspace_mon(mut self, out: String, r: Option<WorkspaceReferenceArg>) -> Self {
+        self.send_action(json!({ "MoveWorkspaceToMonitor": { "output": out, "reference": r } }));
+        self
+    }
+    fn mv_wspace_mon_l(mut self) -> Self {
+        self.send_action(json!({ "MoveWorkspaceToMonitorLeft": {} }));
+        self
+    }
+    fn mv_wspace_mon_r(mut self) -> Self {
+        self.send_action(json!({ "MoveWorkspaceToMonitorRight": {} }));
+        self
+    }
+    fn mv_wspace_mon_u(mut self) -> Self {
+        self.send_action(json!({ "MoveWorkspaceToMonitorUp": {} }));
+        self
+    }
+    fn mv_wspace_mon_d(mut self) -> Self {
+        self.send_action(json!({ "MoveWorkspaceToMonitorDown": {} }));
+        self
+    }
+    fn mv_wspace_mon_prev(mut self) -> Self {
+        self.send_action(json!({ "MoveWorkspaceToMonitorPrevious": {} }));
+        self
+    }
+    fn mv_wspace_mon_next(mut self) -> Self {
+        self.send_action(json!({ "MoveWorkspaceToMonitorNext": {} }));
+        self
+    }
+
+    // -------------------------------------------------------------------------
+    //  Screenshots & Screencasting
+    // -------------------------------------------------------------------------
+
+    // Parent directories of a given path are created first, since niri
+    // silently drops screenshots it can't write.
+    fn snap(mut self, pointer: bool, path: Option<String>) -> Self {
+        create_parent_dir(path.as_deref());
+        self.send_action(json!({ "Screenshot": { "show_pointer": pointer, "path": path } }));
+        self
+    }
+    fn snap_screen(mut self, disk: bool, pointer: bool, path: Option<String>) -> Self {
+        if disk {
+            create_parent_dir(path.as_deref());
+        }
+        self.send_action(json!({
+            "ScreenshotScreen": { "write_to_disk": disk, "show_pointer": pointer, "path": path }
+        }));
+        self
+    }
+    /// Captures the focused output to the clipboard only; nothing is written to disk.
+    fn snap_clipboard(self, pointer: bool) -> Self {
+        self.snap_screen(false, pointer, None)
+    }
+    /// Captures the focused output to `path` (and the clipboard).
+    fn snap_to(self, path: String, pointer: bool) -> Self {
+        self.snap_screen(true, pointer, Some(path))
+    }
+    fn snap_win(mut self, id: Option<u64>, disk: bool, path: Option<String>) -> Self {
+        if disk {
+            create_parent_dir(path.as_deref());
+        }
+        self.send_action(json!({
+            "ScreenshotWindow": { "id": id, "write_to_disk": disk, "path": path }
+        }));
+        self
+    }
+
+    fn cast_win(mut self, id: Option<u64>) -> Self {
+        self.send_action(json!({ "SetDynamicCastWindow": { "id": id } }));
+        self
+    }
+    fn cast_mon(mut self, out: Option<String>) -> Self {
+        self.send_action(json!({ "SetDynamicCastMonitor": { "output": out } }));
+        self
+    }
+    fn cast_clear(mut self) -> Self {
+        self.send_action(json!({ "ClearDynamicCastTarget": {} }));
+        self
+    }
+
+    // -------------------------------------------------------------------------
+    //  System / Misc / Debug
+    // -------------------------------------------------------------------------
+
+    fn inhibit_shortcuts(mut self) -> Self {
+        self.send_action(json!({ "ToggleKeyboardShortcutsInhibit": {} }));
+        self
+    }
+
+    fn transition(mut self, delay: Option<u16>) -> Self {
+        self.send_action(json!({ "DoScreenTransition": { "delay_ms": delay } }));
+        self
+    }
+
+    fn hotkeys(mut self) -> Self {
+        self.send_action(json!({ "ShowHotkeyOverlay": {} }));
+        self
+    }
+
+    // Pointer
+    // niri has no IPC action to move the pointer, so these shell out to
+    // `ydotool`, which needs `ydotoold` running.
+    fn pointer_to(self, x: f64, y: f64) -> Self {
+        self.sh(&format!("ydotool mousemove --absolute -x {x} -y {y}"))
+    }
+    fn pointer_by(self, dx: f64, dy: f64) -> Self {
+        self.sh(&format!("ydotool mousemove -x {dx} -y {dy}"))
+    }
+
+    // Overview
+    fn overview_toggle(mut self) -> Self {
+        self.send_action(json!({ "ToggleOverview": {} }));
+        self
+    }
+    fn overview_open(mut self) -> Self {
+        self.send_action(json!({ "OpenOverview": {} }));
+        self
+    }
+    fn overview_close(mut self) -> Self {
+        self.send_action(json!({ "CloseOverview": {} }));
+        self
+    }
+
+    // Debugging
+    fn dbg_tint(mut self) -> Self {
+        self.send_action(json!({ "ToggleDebugTint": {} }));
+        self
+    }
+    fn dbg_opaque(mut self) -> Self {
+        self.send_action(json!({ "DebugToggleOpaqueRegions": {} }));
+        self
+    }
+    fn dbg_damage(mut self) -> Self {
+        self.send_action(json!({ "DebugToggleDamage": {} }));
+        self
+    }
+}
+
+impl<N: NiriActions> NiriActions for &mut N {
+    fn send_action(&mut self, action: serde_json::Value) {
+        (**self).send_action(action);
+    }
+}
@@ -0,0 +1,59 @@
+/// Where on the output `Niri::float_anchor` places a floating window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Anchor {
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+impl Anchor {
+    /// Top-left corner for something of `size` anchored inside `area`, kept
+    /// `margin` away from the edges it touches.
+    pub(crate) fn position(self, area: (f64, f64), size: (f64, f64), margin: f64) -> (f64, f64) {
+        let place = |area: f64, size: f64, start: bool, end: bool| match (start, end) {
+            (true, _) => margin,
+            (_, true) => area - size - margin,
+            _ => (area - size) / 2.0,
+        };
+        use Anchor::*;
+        let left = matches!(self, TopLeft | Left | BottomLeft);
+        let right = matches!(self, TopRight | Right | BottomRight);
+        let top = matches!(self, TopLeft | Top | TopRight);
+        let bottom = matches!(self, BottomLeft | Bottom | BottomRight);
+        (
+            place(area.0, size.0, left, right),
+            place(area.1, size.1, top, bottom),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn corners_keep_the_margin() {
+        let area = (1920.0, 1080.0);
+        let size = (400.0, 300.0);
+        assert_eq!(Anchor::TopLeft.position(area, size, 10.0), (10.0, 10.0));
+        assert_eq!(
+            Anchor::BottomRight.position(area, size, 10.0),
+            (1510.0, 770.0)
+        );
+    }
+
+    #[test]
+    fn edges_center_the_other_axis() {
+        let area = (1920.0, 1080.0);
+        let size = (400.0, 300.0);
+        assert_eq!(Anchor::Center.position(area, size, 10.0), (760.0, 390.0));
+        assert_eq!(Anchor::Top.position(area, size, 10.0), (760.0, 10.0));
+        assert_eq!(Anchor::Right.position(area, size, 10.0), (1510.0, 390.0));
+    }
+}
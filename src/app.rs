@@ -0,0 +1,152 @@
+use crate::Error;
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use std::path::Path;
+
+pub struct App {
+    pub cmd: &'static str,
+    pub id: &'static str,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct AppDef {
+    pub cmd: String,
+    pub id: String,
+    /// How many of the app's first windows to ignore, e.g. splash screens.
+    #[serde(default)]
+    pub skip_windows: usize,
+    /// Windows smaller than this `(width, height)` are ignored until resized.
+    #[serde(default)]
+    pub min_window_size: Option<(i32, i32)>,
+}
+
+impl AppDef {
+    pub fn new(cmd: impl Into<String>, id: impl Into<String>) -> Self {
+        AppDef {
+            cmd: cmd.into(),
+            id: id.into(),
+            skip_windows: 0,
+            min_window_size: None,
+        }
+    }
+
+    pub fn skip_windows(mut self, n: usize) -> Self {
+        self.skip_windows = n;
+        self
+    }
+
+    pub fn min_window_size(mut self, size: Option<(i32, i32)>) -> Self {
+        self.min_window_size = size;
+        self
+    }
+}
+
+impl From<&App> for AppDef {
+    fn from(app: &App) -> Self {
+        AppDef::new(app.cmd, app.id)
+    }
+}
+
+/// Anything `Niri::spawn` can launch: a command line plus the app_id its window reports.
+pub trait Spawnable {
+    fn cmd(&self) -> &str;
+    fn app_id(&self) -> &str;
+
+    /// How many new windows of the app `spawn` lets pass before it takes one
+    /// as the app's window. Apps that show a splash screen under the same
+    /// app_id set this to skip it.
+    fn skip_windows(&self) -> usize {
+        0
+    }
+
+    /// Smallest `(width, height)` a window needs to count as the app's window
+    /// rather than a splash screen or similar. Smaller windows are looked at
+    /// again when niri reports them resized.
+    fn min_window_size(&self) -> Option<(i32, i32)> {
+        None
+    }
+}
+
+impl<T: Spawnable + ?Sized> Spawnable for &T {
+    fn cmd(&self) -> &str {
+        (**self).cmd()
+    }
+    fn app_id(&self) -> &str {
+        (**self).app_id()
+    }
+    fn skip_windows(&self) -> usize {
+        (**self).skip_windows()
+    }
+    fn min_window_size(&self) -> Option<(i32, i32)> {
+        (**self).min_window_size()
+    }
+}
+
+impl Spawnable for App {
+    fn cmd(&self) -> &str {
+        self.cmd
+    }
+    fn app_id(&self) -> &str {
+        self.id
+    }
+}
+
+impl Spawnable for AppDef {
+    fn cmd(&self) -> &str {
+        &self.cmd
+    }
+    fn app_id(&self) -> &str {
+        &self.id
+    }
+    fn skip_windows(&self) -> usize {
+        self.skip_windows
+    }
+    fn min_window_size(&self) -> Option<(i32, i32)> {
+        self.min_window_size
+    }
+}
+
+/// How waiting for a spawned app's window ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpawnOutcome {
+    /// The app opened the window with this id.
+    Matched(u64),
+    /// niri closed the event stream first.
+    StreamClosed,
+    /// No matching window arrived in time, or reading failed.
+    TimedOut,
+}
+
+impl SpawnOutcome {
+    pub fn window_id(self) -> Option<u64> {
+        match self {
+            SpawnOutcome::Matched(id) => Some(id),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct AppFile {
+    #[serde(default)]
+    app: Vec<AppDef>,
+}
+
+impl App {
+    /// Reads `[[app]]` entries with `cmd` and `id` keys from a TOML file, or
+    /// from a JSON file of the same shape when the extension is `.json`.
+    pub fn load_from(path: impl AsRef<Path>) -> Result<Vec<AppDef>, Error> {
+        let file: AppFile = read_config(path.as_ref())?;
+        Ok(file.app)
+    }
+}
+
+/// Parses `path` as JSON if it has a `.json` extension, and as TOML otherwise.
+pub(crate) fn read_config<T: DeserializeOwned>(path: &Path) -> Result<T, Error> {
+    let contents = std::fs::read_to_string(path)?;
+    if path.extension().is_some_and(|ext| ext == "json") {
+        Ok(serde_json::from_str(&contents)?)
+    } else {
+        Ok(toml::from_str(&contents)?)
+    }
+}
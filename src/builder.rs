@@ -0,0 +1,139 @@
+use crate::{Error, Niri};
+use std::time::Duration;
+
+/// Smallest buffer `event_buffer_size` accepts.
+const MIN_EVENT_BUFFER_SIZE: usize = 256;
+
+pub struct NiriBuilder {
+    pub(crate) timeout: Option<Duration>,
+    pub(crate) socket_path: Option<String>,
+    pub(crate) reconnect: bool,
+    pub(crate) dry_run: bool,
+    pub(crate) action_timeout: Option<Duration>,
+    pub(crate) retries: u32,
+    pub(crate) retry_backoff: Duration,
+    pub(crate) min_action_gap: Duration,
+    pub(crate) event_stream: bool,
+    pub(crate) event_buffer_size: usize,
+    pub(crate) skip_initial_sync: bool,
+    pub(crate) action_log: bool,
+    pub(crate) action_log_capacity: Option<usize>,
+}
+
+impl Default for NiriBuilder {
+    fn default() -> Self {
+        NiriBuilder {
+            timeout: None,
+            socket_path: None,
+            reconnect: false,
+            dry_run: false,
+            action_timeout: None,
+            retries: 2,
+            retry_backoff: Duration::from_millis(50),
+            min_action_gap: Duration::ZERO,
+            event_stream: true,
+            event_buffer_size: 8 * 1024,
+            skip_initial_sync: false,
+            action_log: false,
+            action_log_capacity: None,
+        }
+    }
+}
+
+impl NiriBuilder {
+    /// How long `spawn` and other waits without a timeout of their own wait
+    /// for each event. Defaults to 3 seconds. Open-ended readers such as
+    /// `events` never time out.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Socket to connect to instead of `$NIRI_SOCKET`.
+    pub fn socket_path(mut self, path: impl Into<String>) -> Self {
+        self.socket_path = Some(path.into());
+        self
+    }
+
+    /// Re-subscribe to the event stream if niri closes it.
+    pub fn reconnect(mut self, reconnect: bool) -> Self {
+        self.reconnect = reconnect;
+        self
+    }
+
+    /// Print actions to stderr instead of sending them. Queries still go to niri.
+    pub fn dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Read/write timeout for each individual action or query.
+    pub fn action_timeout(mut self, timeout: Duration) -> Self {
+        self.action_timeout = Some(timeout);
+        self
+    }
+
+    /// How many times a request is re-sent after the socket is reset mid-write.
+    pub fn retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    /// Delay before the first retry; doubled for each one after it.
+    pub fn retry_backoff(mut self, backoff: Duration) -> Self {
+        self.retry_backoff = backoff;
+        self
+    }
+
+    /// Least time between two actions; sending sleeps until it has passed.
+    /// Defaults to zero.
+    pub fn min_action_gap(mut self, gap: Duration) -> Self {
+        self.min_action_gap = gap;
+        self
+    }
+
+    /// Subscribe to niri's event stream. Without it nothing can wait for
+    /// windows or other events, but connecting is cheaper.
+    pub fn event_stream(mut self, event_stream: bool) -> Self {
+        self.event_stream = event_stream;
+        self
+    }
+
+    /// Buffer size for reading the event stream, in bytes. Defaults to 8 KiB;
+    /// a larger buffer helps with the big `WindowsChanged` bursts of busy
+    /// sessions. Sizes below 256 bytes are raised to 256, since an empty
+    /// buffer would read as the stream closing.
+    pub fn event_buffer_size(mut self, bytes: usize) -> Self {
+        self.event_buffer_size = bytes.max(MIN_EVENT_BUFFER_SIZE);
+        self
+    }
+
+    /// Don't wait for niri's window snapshot while connecting. Scripts that
+    /// never spawn or wait for windows connect faster this way; `spawn` reads
+    /// the snapshot the first time it needs it. `focus_back` only knows about
+    /// focus changes from then on.
+    pub fn skip_initial_sync(mut self, skip: bool) -> Self {
+        self.skip_initial_sync = skip;
+        self
+    }
+
+    /// Keep a log of every action sent, with its time and niri's reply,
+    /// readable through `Niri::action_log`. Off by default, since the log
+    /// grows with every action unless `action_log_capacity` bounds it.
+    pub fn action_log(mut self, action_log: bool) -> Self {
+        self.action_log = action_log;
+        self
+    }
+
+    /// Turns the action log on, keeping only the `capacity` most recent
+    /// actions. A capacity of 0 turns it off.
+    pub fn action_log_capacity(mut self, capacity: usize) -> Self {
+        self.action_log = capacity > 0;
+        self.action_log_capacity = Some(capacity);
+        self
+    }
+
+    pub fn connect(self) -> Result<Niri, Error> {
+        Niri::connect_with(self)
+    }
+}
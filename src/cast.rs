@@ -0,0 +1,6 @@
+/// What niri's dynamic cast target is showing.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum CastTarget {
+    Window(u64),
+    Output(String),
+}
@@ -0,0 +1,79 @@
+use niri_ipc::Window;
+
+/// One column of the scrolling layout, as returned by `Niri::columns`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Column {
+    /// Position of the column on its workspace, starting at 1 like niri's
+    /// column indices.
+    pub index: usize,
+    /// Ids of the column's windows, top to bottom.
+    pub windows: Vec<u64>,
+    /// Width of the column's tiles in logical pixels.
+    pub width: f64,
+}
+
+/// Groups the tiled windows of `workspace_id` into columns, left to right.
+pub(crate) fn group(windows: &[Window], workspace_id: u64) -> Vec<Column> {
+    let mut tiles: Vec<(usize, usize, &Window)> = windows
+        .iter()
+        .filter(|w| w.workspace_id == Some(workspace_id))
+        .filter_map(|w| {
+            let (col, row) = w.layout.pos_in_scrolling_layout?;
+            Some((col, row, w))
+        })
+        .collect();
+    tiles.sort_by_key(|&(col, row, _)| (col, row));
+
+    let mut columns: Vec<Column> = Vec::new();
+    for (col, _, window) in tiles {
+        match columns.last_mut() {
+            Some(column) if column.index == col => {
+                column.windows.push(window.id);
+                column.width = column.width.max(window.layout.tile_size.0);
+            }
+            _ => columns.push(Column {
+                index: col,
+                windows: vec![window.id],
+                width: window.layout.tile_size.0,
+            }),
+        }
+    }
+    columns
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::window;
+
+    #[test]
+    fn groups_tiles_by_column_top_to_bottom() {
+        let windows = [
+            window(1, "foot", 1, Some((2, 2)), 600.0),
+            window(2, "foot", 1, Some((1, 1)), 800.0),
+            window(3, "foot", 1, Some((2, 1)), 640.0),
+            window(4, "foot", 1, None, 300.0),
+            window(5, "foot", 2, Some((1, 1)), 800.0),
+        ];
+        assert_eq!(
+            group(&windows, 1),
+            vec![
+                Column {
+                    index: 1,
+                    windows: vec![2],
+                    width: 800.0,
+                },
+                Column {
+                    index: 2,
+                    windows: vec![3, 1],
+                    width: 640.0,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn empty_workspace_has_no_columns() {
+        assert!(group(&[window(1, "foot", 1, Some((1, 1)), 800.0)], 2).is_empty());
+    }
+}
@@ -0,0 +1,120 @@
+use crate::{Error, NiriActions};
+use niri_ipc::{Action, Reply, Request, Response};
+use serde_json::json;
+use std::io::{BufRead, BufReader, ErrorKind, Write};
+use std::os::unix::net::UnixStream;
+use std::time::Duration;
+
+/// The sending half of a `Niri` connection. It holds no event stream, so it
+/// is cheap to clone and can be moved to another thread while the `Niri` it
+/// came from keeps reading events.
+#[derive(Debug, Clone)]
+pub struct CommandHandle {
+    pub(crate) socket_path: String,
+    pub(crate) dry_run: bool,
+    pub(crate) action_timeout: Option<Duration>,
+    pub(crate) retries: u32,
+    pub(crate) retry_backoff: Duration,
+}
+
+impl CommandHandle {
+    /// Sends `action`, panicking if niri can't be reached. Named apart from
+    /// `NiriActions::send_action`, which takes the action as JSON.
+    pub fn send_action_typed(&self, action: Action) {
+        self.send_action_json(serde_json::to_value(action).expect("actions serialize"));
+    }
+
+    /// Sends `action` and returns niri's reply as-is, including `{"Err": ...}`
+    /// replies. Only failures to talk to niri at all are returned as errors.
+    /// In dry-run mode nothing is sent and the reply is `null`.
+    pub fn send_action_raw(&self, action: Action) -> Result<serde_json::Value, Error> {
+        self.send_action_value(serde_json::to_value(action)?)
+    }
+
+    /// Sends `action` and checks niri's reply. An action the running niri
+    /// doesn't know comes back as `Error::UnsupportedAction`, so callers can
+    /// fall back to something older. Dry-run mode always succeeds.
+    pub fn try_action(&self, action: Action) -> Result<(), Error> {
+        let reply = self.send_action_raw(action)?;
+        if reply.is_null() {
+            return Ok(());
+        }
+        let reply: Reply = serde_json::from_value(reply)?;
+        reply.map(|_| ()).map_err(Error::from_niri_reply)
+    }
+
+    pub(crate) fn send_action_json(&self, json_val: serde_json::Value) {
+        if let Err(e) = self.send_action_value(json_val) {
+            panic!("Failed to send action to niri: {e}");
+        }
+    }
+
+    pub(crate) fn send_action_value(
+        &self,
+        json_val: serde_json::Value,
+    ) -> Result<serde_json::Value, Error> {
+        if self.dry_run {
+            eprintln!("niri action (dry run): {json_val}");
+            return Ok(serde_json::Value::Null);
+        }
+        self.roundtrip(&json!({ "Action": json_val }))
+    }
+
+    pub(crate) fn request(&self, req: Request) -> Result<Response, Error> {
+        let reply: Reply = serde_json::from_value(self.roundtrip(&serde_json::to_value(req)?)?)?;
+        reply.map_err(Error::Niri)
+    }
+
+    /// Sends one request on a fresh socket, re-sending it with exponential
+    /// backoff if niri resets the connection mid-write. A reset while reading
+    /// the reply is returned as is: niri may already have applied the request,
+    /// and sending a toggle twice would undo it.
+    fn roundtrip(&self, payload: &serde_json::Value) -> Result<serde_json::Value, Error> {
+        let mut backoff = self.retry_backoff;
+        for attempt in 0..=self.retries {
+            match self.write_request(payload) {
+                Err(Error::Io(e))
+                    if matches!(e.kind(), ErrorKind::BrokenPipe | ErrorKind::ConnectionReset) =>
+                {
+                    if attempt < self.retries {
+                        std::thread::sleep(backoff);
+                        backoff *= 2;
+                    }
+                }
+                Ok(stream) => return read_reply(stream),
+                Err(e) => return Err(e),
+            }
+        }
+        Err(Error::Disconnected)
+    }
+
+    fn write_request(&self, payload: &serde_json::Value) -> Result<UnixStream, Error> {
+        let mut stream = connect_socket(&self.socket_path)?;
+        stream.set_read_timeout(self.action_timeout)?;
+        stream.set_write_timeout(self.action_timeout)?;
+        stream.write_all(payload.to_string().as_bytes())?;
+        stream.write_all(b"\n")?;
+        Ok(stream)
+    }
+}
+
+fn read_reply(stream: UnixStream) -> Result<serde_json::Value, Error> {
+    let mut line = String::new();
+    BufReader::new(stream).read_line(&mut line)?;
+    Ok(serde_json::from_str(&line)?)
+}
+
+impl NiriActions for CommandHandle {
+    fn send_action(&mut self, action: serde_json::Value) {
+        self.send_action_json(action);
+    }
+}
+
+pub(crate) fn connect_socket(path: &str) -> Result<UnixStream, Error> {
+    UnixStream::connect(path).map_err(|e| match e.kind() {
+        ErrorKind::NotFound | ErrorKind::ConnectionRefused => Error::SocketUnavailable {
+            path: path.to_string(),
+        },
+        _ => Error::Io(e),
+    })
+}
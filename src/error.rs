@@ -0,0 +1,89 @@
+use std::fmt;
+
+#[derive(Debug)]
+pub enum Error {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    Toml(toml::de::Error),
+    Niri(String),
+    /// niri could not parse the action, usually because it predates it.
+    UnsupportedAction(String),
+    UnexpectedResponse,
+    Disconnected,
+    EventStreamRequired,
+    SocketNotSet,
+    SocketUnavailable {
+        path: String,
+    },
+    Unsupported,
+    InvalidArgument(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "I/O error: {e}"),
+            Error::Json(e) => write!(f, "malformed JSON: {e}"),
+            Error::Toml(e) => write!(f, "malformed TOML: {e}"),
+            Error::Niri(msg) => write!(f, "niri returned an error: {msg}"),
+            Error::UnsupportedAction(msg) => {
+                write!(f, "action not supported by this niri: {msg}")
+            }
+            Error::UnexpectedResponse => write!(f, "unexpected response from niri"),
+            Error::Disconnected => write!(f, "lost connection to niri"),
+            Error::EventStreamRequired => {
+                write!(f, "needs niri's event stream, but connected without it")
+            }
+            Error::SocketNotSet => write!(f, "NIRI_SOCKET not set"),
+            Error::SocketUnavailable { path } => {
+                write!(f, "niri socket {path} is unavailable; is niri running?")
+            }
+            Error::Unsupported => write!(f, "not supported by niri's IPC"),
+            Error::InvalidArgument(msg) => write!(f, "{msg}"),
+        }
+    }
+}
+
+impl Error {
+    /// Classifies an `Err` reply from niri. An action this niri doesn't know
+    /// fails to deserialize with "unknown variant", which is reported as
+    /// `UnsupportedAction`. Other parse errors, such as a wrong argument type
+    /// or an out-of-range index, are caller bugs and stay `Niri`; unknown
+    /// fields are ignored by niri rather than rejected.
+    pub(crate) fn from_niri_reply(msg: String) -> Error {
+        if msg.contains("unknown variant") {
+            Error::UnsupportedAction(msg)
+        } else {
+            Error::Niri(msg)
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(e) => Some(e),
+            Error::Json(e) => Some(e),
+            Error::Toml(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Self {
+        Error::Json(e)
+    }
+}
+
+impl From<toml::de::Error> for Error {
+    fn from(e: toml::de::Error) -> Self {
+        Error::Toml(e)
+    }
+}
@@ -0,0 +1,104 @@
+use niri_ipc::{Window, WindowLayout};
+
+/// The variant of a `niri_ipc::Event`, without its payload.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EventKind {
+    WorkspacesChanged,
+    WorkspaceUrgencyChanged,
+    WorkspaceActivated,
+    WorkspaceActiveWindowChanged,
+    WindowsChanged,
+    WindowOpenedOrChanged,
+    WindowClosed,
+    WindowFocusChanged,
+    WindowFocusTimestampChanged,
+    WindowUrgencyChanged,
+    WindowLayoutsChanged,
+    KeyboardLayoutsChanged,
+    KeyboardLayoutSwitched,
+    OverviewOpenedOrClosed,
+    ConfigLoaded,
+    ScreenshotCaptured,
+}
+
+impl EventKind {
+    /// Whether `Niri` updates its own state from events of this kind, so they
+    /// must be read even when the caller filters them out.
+    pub(crate) fn is_observed(self) -> bool {
+        matches!(
+            self,
+            EventKind::WindowsChanged
+                | EventKind::WindowOpenedOrChanged
+                | EventKind::WindowClosed
+                | EventKind::WindowFocusChanged
+        )
+    }
+
+    /// Reads the kind from the tag of a raw event line such as
+    /// `{"WindowClosed":{"id":3}}`, without parsing the rest of it.
+    pub(crate) fn of_line(line: &str) -> Option<EventKind> {
+        let rest = line
+            .trim_start()
+            .strip_prefix('{')?
+            .trim_start()
+            .strip_prefix('"')?;
+        let (tag, _) = rest.split_once('"')?;
+        Some(match tag {
+            "WorkspacesChanged" => EventKind::WorkspacesChanged,
+            "WorkspaceUrgencyChanged" => EventKind::WorkspaceUrgencyChanged,
+            "WorkspaceActivated" => EventKind::WorkspaceActivated,
+            "WorkspaceActiveWindowChanged" => EventKind::WorkspaceActiveWindowChanged,
+            "WindowsChanged" => EventKind::WindowsChanged,
+            "WindowOpenedOrChanged" => EventKind::WindowOpenedOrChanged,
+            "WindowClosed" => EventKind::WindowClosed,
+            "WindowFocusChanged" => EventKind::WindowFocusChanged,
+            "WindowFocusTimestampChanged" => EventKind::WindowFocusTimestampChanged,
+            "WindowUrgencyChanged" => EventKind::WindowUrgencyChanged,
+            "WindowLayoutsChanged" => EventKind::WindowLayoutsChanged,
+            "KeyboardLayoutsChanged" => EventKind::KeyboardLayoutsChanged,
+            "KeyboardLayoutSwitched" => EventKind::KeyboardLayoutSwitched,
+            "OverviewOpenedOrClosed" => EventKind::OverviewOpenedOrClosed,
+            "ConfigLoaded" => EventKind::ConfigLoaded,
+            "ScreenshotCaptured" => EventKind::ScreenshotCaptured,
+            _ => return None,
+        })
+    }
+}
+
+/// A change to one window, as reported to `Niri::watch_window`.
+#[derive(Debug, Clone)]
+pub enum WindowEvent {
+    /// The window opened, or its title, app_id, workspace or other state changed.
+    Changed(Window),
+    Focused,
+    Unfocused,
+    UrgencyChanged(bool),
+    LayoutChanged(WindowLayout),
+    /// The window closed. No more events follow.
+    Closed,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_the_tag_of_an_event_line() {
+        assert_eq!(
+            EventKind::of_line(r#"{"WindowClosed":{"id":3}}"#),
+            Some(EventKind::WindowClosed)
+        );
+        assert_eq!(
+            EventKind::of_line("  { \"OverviewOpenedOrClosed\":{\"is_open\":true}}\n"),
+            Some(EventKind::OverviewOpenedOrClosed)
+        );
+    }
+
+    #[test]
+    fn unknown_or_malformed_lines_have_no_kind() {
+        assert_eq!(EventKind::of_line(r#"{"SomethingNew":{}}"#), None);
+        assert_eq!(EventKind::of_line(""), None);
+        assert_eq!(EventKind::of_line("\"WindowClosed\""), None);
+        assert_eq!(EventKind::of_line(r#"{"WindowClosed"#), None);
+    }
+}
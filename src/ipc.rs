@@ -1,763 +1,1906 @@
+use crate::action_log::ActionLog;
+use crate::column;
+use crate::command::connect_socket;
+use crate::{
+    Anchor, CastTarget, Column, CommandHandle, Error, EventKind, LayoutSnapshot, LoggedAction,
+    NiriActions, NiriBuilder, SpawnOutcome, Spawnable, WindowEvent,
+};
 use niri_ipc::{
-    ColumnDisplay, LayoutSwitchTarget, PositionChange, SizeChange, WorkspaceReferenceArg,
+    Action, ColumnDisplay, Event, Output, OutputAction, OutputConfigChanged, PositionChange,
+    Request, Response, ScaleToSet, Window, Workspace, WorkspaceReferenceArg,
 };
 use serde_json::json;
-use std::collections::HashSet;
-use std::io::{BufRead, BufReader, Write};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::ops::{ControlFlow, Deref, DerefMut};
 use std::os::unix::net::UnixStream;
-use std::time::Duration;
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
+use std::time::{Duration, Instant};
+
+/// How many focus changes `focus_back` can reach.
+const FOCUS_HISTORY_LEN: usize = 64;
 
 pub struct Niri {
-    socket_path: String,
-    event_reader: BufReader<UnixStream>,
+    commands: CommandHandle,
+    event_reader: Option<BufReader<EventSource>>,
+    pending_line: String,
+    stream_closed: bool,
     seen_windows: HashSet<u64>,
+    reconnect: bool,
+    missing_windows: Vec<String>,
+    focus_history: VecDeque<u64>,
+    monitors_powered: Option<bool>,
+    recording: Option<File>,
+    min_action_gap: Duration,
+    last_action: Option<Instant>,
+    column_display: ColumnDisplay,
+    shortcuts_inhibited: bool,
+    cast_target: Option<CastTarget>,
+    action_log: Option<ActionLog>,
+    synced: bool,
+    /// How long waits without a deadline of their own wait for each event.
+    /// Outside of waits the event socket blocks, so open-ended readers such
+    /// as `events` only stop when the stream ends.
+    read_timeout: Option<Duration>,
 }
 
-pub struct App {
-    pub cmd: &'static str,
-    pub id: &'static str,
+impl NiriActions for Niri {
+    fn send_action(&mut self, action: serde_json::Value) {
+        if let Some(last) = self.last_action {
+            std::thread::sleep(self.min_action_gap.saturating_sub(last.elapsed()));
+        }
+        self.track_action(&action);
+        match &mut self.action_log {
+            Some(log) => {
+                let result = self.commands.send_action_value(action.clone());
+                log.push(action, &result);
+                if let Err(e) = result {
+                    panic!("Failed to send action to niri: {e}");
+                }
+            }
+            None => self.commands.send_action_json(action),
+        }
+        self.last_action = Some(Instant::now());
+    }
 }
 
 impl Niri {
     pub fn connect(timeout: Option<Duration>) -> Self {
-        let socket_path = std::env::var("NIRI_SOCKET").expect("NIRI_SOCKET not set");
-        let mut stream =
-            UnixStream::connect(&socket_path).expect("Failed to connect to NIRI_SOCKET");
-        stream
-            .set_read_timeout(timeout.unwrap_or(Duration::from_secs(3)).into())
-            .unwrap();
-        stream.write_all(b"\"EventStream\"\n").unwrap();
-        let mut niri = Niri {
+        let builder = Niri::builder();
+        match timeout {
+            Some(timeout) => builder.timeout(timeout).connect(),
+            None => builder.connect(),
+        }
+        .unwrap_or_else(|e| panic!("Failed to connect to niri: {e}"))
+    }
+
+    /// Connects without subscribing to niri's event stream, which is all a
+    /// script that only sends actions and queries needs. `spawn`, the
+    /// `wait_*` methods, `on_event` and `watch_window` panic with
+    /// `Error::EventStreamRequired` on such a connection; the `events`
+    /// iterators are empty.
+    pub fn connect_actions_only(timeout: Option<Duration>) -> Self {
+        let builder = Niri::builder().event_stream(false);
+        match timeout {
+            Some(timeout) => builder.action_timeout(timeout).connect(),
+            None => builder.connect(),
+        }
+        .unwrap_or_else(|e| panic!("Failed to connect to niri: {e}"))
+    }
+
+    pub fn builder() -> NiriBuilder {
+        NiriBuilder::default()
+    }
+
+    pub(crate) fn connect_with(builder: NiriBuilder) -> Result<Self, Error> {
+        let socket_path = match builder.socket_path {
+            Some(path) => path,
+            None => std::env::var("NIRI_SOCKET").map_err(|_| Error::SocketNotSet)?,
+        };
+        let timeout = builder.timeout.unwrap_or(Duration::from_secs(3));
+        let event_reader = if builder.event_stream {
+            Some(open_event_stream(&socket_path, builder.event_buffer_size)?)
+        } else {
+            None
+        };
+        let commands = CommandHandle {
             socket_path,
-            event_reader: BufReader::new(stream),
-            seen_windows: HashSet::new(),
+            dry_run: builder.dry_run,
+            action_timeout: builder.action_timeout,
+            retries: builder.retries,
+            retry_backoff: builder.retry_backoff,
+        };
+        let mut niri = Niri::with_event_reader(commands, event_reader, builder.reconnect);
+        niri.read_timeout = Some(timeout);
+        niri.min_action_gap = builder.min_action_gap;
+        niri.action_log = builder
+            .action_log
+            .then(|| ActionLog::new(builder.action_log_capacity));
+        if !builder.skip_initial_sync {
+            niri.sync_initial_state();
+        }
+        Ok(niri)
+    }
+
+    /// Replays a file written by `record_events` through the same code paths
+    /// as a live event stream, for testing scripts without a compositor.
+    /// Actions are printed to stderr instead of sent, queries fail, and the
+    /// stream ends at the end of the file.
+    pub fn from_recording(path: impl AsRef<Path>) -> Self {
+        let path = path.as_ref();
+        let file =
+            File::open(path).unwrap_or_else(|e| panic!("Failed to open {}: {e}", path.display()));
+        let commands = CommandHandle {
+            socket_path: String::new(),
+            dry_run: true,
+            action_timeout: None,
+            retries: 0,
+            retry_backoff: Duration::ZERO,
         };
+        let event_reader = Some(BufReader::new(EventSource::Replay(file)));
+        let mut niri = Niri::with_event_reader(commands, event_reader, false);
         niri.sync_initial_state();
         niri
     }
 
-    fn sync_initial_state(&mut self) {
-        self.event_reader
-            .get_ref()
-            .set_read_timeout(Some(Duration::from_millis(100)))
-            .unwrap();
-
-        let mut line = String::new();
-        while self.event_reader.read_line(&mut line).is_ok() && !line.trim().is_empty() {
-            if let Ok(json) = serde_json::from_str::<serde_json::Value>(&line) {
-                if let Some(windows) = json.get("WindowsChanged").and_then(|w| w.as_array()) {
-                    for w in windows {
-                        if let Some(id) = w.get("id").and_then(|i| i.as_u64()) {
-                            self.seen_windows.insert(id);
-                        }
-                    }
-                }
-            }
-            line.clear();
+    fn with_event_reader(
+        commands: CommandHandle,
+        event_reader: Option<BufReader<EventSource>>,
+        reconnect: bool,
+    ) -> Self {
+        Niri {
+            commands,
+            event_reader,
+            pending_line: String::new(),
+            stream_closed: false,
+            seen_windows: HashSet::new(),
+            reconnect,
+            missing_windows: Vec::new(),
+            focus_history: VecDeque::new(),
+            monitors_powered: None,
+            recording: None,
+            min_action_gap: Duration::ZERO,
+            last_action: None,
+            column_display: ColumnDisplay::Normal,
+            shortcuts_inhibited: false,
+            cast_target: None,
+            action_log: None,
+            synced: false,
+            read_timeout: None,
         }
-        self.event_reader.get_ref().set_read_timeout(None).unwrap();
     }
 
-    fn send_action(&self, json_val: serde_json::Value) {
-        let mut stream = UnixStream::connect(&self.socket_path).unwrap();
-        let payload = json!({ "Action": json_val });
-        stream.write_all(payload.to_string().as_bytes()).unwrap();
-        stream.write_all(b"\n").unwrap();
-        let _ = std::io::Read::read(&mut stream, &mut [0; 1024]);
+    /// Panics with `Error::EventStreamRequired` on a connection without an
+    /// event stream, rather than letting a wait return as if it succeeded.
+    fn require_event_stream(&self, what: &str) {
+        if self.event_reader.is_none() {
+            panic!("Failed to {what}: {}", Error::EventStreamRequired);
+        }
     }
 
-    pub fn spawn(mut self, app: &App) -> Self {
-        let cmd_vec: Vec<&str> = app.cmd.split_whitespace().collect();
-        self.send_action(json!({ "Spawn": { "command": cmd_vec } }));
+    fn reopen_event_stream(&mut self) -> Result<(), Error> {
+        let Some(reader) = &self.event_reader else {
+            return Err(Error::EventStreamRequired);
+        };
+        let capacity = reader.capacity();
+        self.event_reader = Some(open_event_stream(&self.commands.socket_path, capacity)?);
+        self.pending_line.clear();
+        self.stream_closed = false;
+        self.sync_initial_state();
+        Ok(())
+    }
 
-        let mut line = String::new();
-        loop {
-            line.clear();
-            if self.event_reader.read_line(&mut line).is_err() {
-                break;
-            }
-            let Ok(json) = serde_json::from_str::<serde_json::Value>(&line) else {
-                continue;
+    /// Updates the state niri can't be asked about from an action about to
+    /// be sent.
+    fn track_action(&mut self, action: &serde_json::Value) {
+        if action.get("ToggleKeyboardShortcutsInhibit").is_some() {
+            self.shortcuts_inhibited = !self.shortcuts_inhibited;
+        } else if let Some(cast) = action.get("SetDynamicCastWindow") {
+            let id = match cast["id"].as_u64() {
+                Some(id) => Some(id),
+                None => self.focused_window().ok().flatten().map(|w| w.id),
+            };
+            self.cast_target = id.map(CastTarget::Window);
+        } else if let Some(cast) = action.get("SetDynamicCastMonitor") {
+            let output = match cast["output"].as_str() {
+                Some(output) => Some(output.to_string()),
+                None => self.focused_output().ok().flatten().map(|o| o.name),
             };
+            self.cast_target = output.map(CastTarget::Output);
+        } else if action.get("ClearDynamicCastTarget").is_some() {
+            self.cast_target = None;
+        }
+    }
 
-            if let Some(wrapper) = json.get("WindowOpenedOrChanged") {
-                let win = wrapper.get("window").unwrap_or(wrapper);
-                if let Some(id) = win.get("id").and_then(|i| i.as_u64()) {
-                    if self.seen_windows.contains(&id) {
-                        continue;
-                    }
-                    if let Some(aid) = win.get("app_id").and_then(|s| s.as_str()) {
-                        if aid == app.id {
-                            self.seen_windows.insert(id);
-                            return self;
-                        }
+    fn sync_initial_state(&mut self) {
+        // niri opens the stream with a WindowsChanged snapshot; that is all we need.
+        // Everything after it stays buffered for spawn and friends to read.
+        if let Some(reader) = &self.event_reader {
+            let _ = reader.get_ref().set_read_timeout(self.read_timeout);
+        }
+        let mut niri = TimeoutGuard(self);
+        loop {
+            match niri.read_event_line() {
+                Ok(None) => break,
+                Ok(Some(line)) if line.trim().is_empty() => continue,
+                Ok(Some(line)) => {
+                    if let Ok(Event::WindowsChanged { windows }) = serde_json::from_str(&line) {
+                        niri.sync_from(&windows);
+                        break;
                     }
                 }
+                Err(_) => break,
             }
         }
-        self
+        niri.synced = true;
     }
 
-    pub fn spawn_args(self, cmd: Vec<String>) -> Self {
-        self.send_action(json!({ "Spawn": { "command": cmd } }));
-        self
+    /// Takes in niri's opening window snapshot.
+    fn sync_from(&mut self, windows: &[Window]) {
+        self.seen_windows.extend(windows.iter().map(|w| w.id));
+        if let Some(w) = windows.iter().find(|w| w.is_focused) {
+            self.record_focus(w.id);
+        }
+        self.synced = true;
     }
 
-    pub fn sh(self, cmd: &str) -> Self {
-        self.send_action(json!({ "SpawnSh": { "command": cmd } }));
-        self
+    /// Sends `action` and returns niri's reply as-is, including `{"Err": ...}`
+    /// replies. Only failures to talk to niri at all are returned as errors.
+    /// In dry-run mode nothing is sent and the reply is `null`.
+    pub fn send_action_raw(&self, action: Action) -> Result<serde_json::Value, Error> {
+        self.commands.send_action_raw(action)
     }
 
-    pub fn call<F>(mut self, func: F) -> Self
-    where
-        F: FnOnce(&mut Self),
-    {
-        func(&mut self);
-        self
+    /// Sends `action` and checks niri's reply; see `CommandHandle::try_action`.
+    pub fn try_action(&self, action: Action) -> Result<(), Error> {
+        self.commands.try_action(action)
     }
 
-    pub fn quit(self, skip_confirm: bool) -> Self {
-        self.send_action(json!({ "Quit": { "skip_confirmation": skip_confirm } }));
-        self
+    /// Sends any IPC request and returns niri's response, for requests that
+    /// have no wrapper here. An `Err` reply becomes `Error::Niri`. Requests
+    /// are sent even in dry-run mode.
+    pub fn request(&mut self, req: Request) -> Result<Response, Error> {
+        self.commands.request(req)
     }
 
-    pub fn reload_config(self) -> Self {
-        self.send_action(json!({ "LoadConfigFile": {} }));
-        self
+    /// Every action sent so far, oldest first, if the connection was built
+    /// with `NiriBuilder::action_log`; nothing otherwise. Collect it into a
+    /// `Vec` to serialize it with `serde_json::to_string`. Actions sent
+    /// through a `CommandHandle` aren't included.
+    pub fn action_log(&self) -> impl Iterator<Item = &LoggedAction> {
+        self.action_log.iter().flat_map(|log| log.entries())
     }
 
-    // -------------------------------------------------------------------------
-    //  Focus Actions
-    // -------------------------------------------------------------------------
-
-    // Basic Directional
-    pub fn foc_l(self) -> Self {
-        self.send_action(json!({ "FocusColumnLeft": {} }));
-        self
-    }
-    pub fn foc_r(self) -> Self {
-        self.send_action(json!({ "FocusColumnRight": {} }));
-        self
-    }
-    pub fn foc_u(self) -> Self {
-        self.send_action(json!({ "FocusWindowUp": {} }));
-        self
-    }
-    pub fn foc_d(self) -> Self {
-        self.send_action(json!({ "FocusWindowDown": {} }));
-        self
+    /// A cloneable handle for sending actions from another thread while this
+    /// `Niri` keeps the event stream.
+    pub fn command_handle(&self) -> CommandHandle {
+        self.commands.clone()
     }
 
-    // Absolute / Specific
-    pub fn foc_id(self, id: u64) -> Self {
-        self.send_action(json!({ "FocusWindow": { "id": id } }));
-        self
-    }
-    pub fn foc_idx(self, idx: u8) -> Self {
-        self.send_action(json!({ "FocusWindowInColumn": { "index": idx } }));
-        self
-    }
-    pub fn foc_prev(self) -> Self {
-        self.send_action(json!({ "FocusWindowPrevious": {} }));
-        self
-    }
-    pub fn foc_top(self) -> Self {
-        self.send_action(json!({ "FocusWindowTop": {} }));
-        self
-    }
-    pub fn foc_bottom(self) -> Self {
-        self.send_action(json!({ "FocusWindowBottom": {} }));
+    /// Launches `app` and waits for its window, for up to `timeout` or, without
+    /// one, the connection's read timeout.
+    pub fn spawn(mut self, app: &impl Spawnable, timeout: Option<Duration>) -> Self {
+        self.spawn_result(app, timeout);
         self
     }
 
-    // Column Traversal
-    pub fn foc_col_idx(self, idx: usize) -> Self {
-        self.send_action(json!({ "FocusColumn": { "index": idx } }));
-        self
-    }
-    pub fn foc_col_first(self) -> Self {
-        self.send_action(json!({ "FocusColumnFirst": {} }));
+    /// Like `spawn`, but moves the new window back to the workspace that was
+    /// focused when it was launched if a window rule placed it elsewhere.
+    pub fn spawn_here(mut self, app: &impl Spawnable, timeout: Option<Duration>) -> Self {
+        let here = self
+            .workspaces()
+            .expect("Failed to query workspaces")
+            .into_iter()
+            .find(|w| w.is_focused);
+        let Some(id) = self.spawn_result(app, timeout).window_id() else {
+            return self;
+        };
+        let Some(here) = here else {
+            return self;
+        };
+        let landed = self
+            .windows()
+            .expect("Failed to query windows")
+            .into_iter()
+            .find(|w| w.id == id)
+            .and_then(|w| w.workspace_id);
+        if landed.is_some_and(|ws| ws != here.id) {
+            self = self.mv_win_wspace(Some(id), WorkspaceReferenceArg::Id(here.id), true);
+        }
         self
     }
-    pub fn foc_col_last(self) -> Self {
-        self.send_action(json!({ "FocusColumnLast": {} }));
-        self
+
+    /// Like `spawn`, but reports whether the app's window showed up.
+    pub fn spawn_result(
+        &mut self,
+        app: &impl Spawnable,
+        timeout: Option<Duration>,
+    ) -> SpawnOutcome {
+        self.require_event_stream(&format!("spawn {}", app.cmd()));
+        if !self.synced {
+            // Connected with `skip_initial_sync`: catch up now, and count
+            // every window niri has reported since as already open.
+            self.sync_initial_state();
+            self.drain_events();
+        }
+        let cmd_vec: Vec<&str> = app.cmd().split_whitespace().collect();
+        self.send_action(json!({ "Spawn": { "command": cmd_vec } }));
+
+        let deadline = timeout.map(|t| Instant::now() + t);
+        let mut to_skip = app.skip_windows();
+        let big_enough = |(w, h): (i32, i32)| {
+            app.min_window_size()
+                .is_none_or(|(min_w, min_h)| w >= min_w && h >= min_h)
+        };
+        // The app's windows that opened too small, in case niri resizes them.
+        let mut undersized: Vec<u64> = Vec::new();
+        while let Some(event) = self.next_event_until(deadline) {
+            let (id, size) = match event {
+                Event::WindowOpenedOrChanged { window }
+                    if window.app_id.as_deref() == Some(app.app_id())
+                        && !self.seen_windows.contains(&window.id) =>
+                {
+                    if to_skip > 0 {
+                        to_skip -= 1;
+                        self.seen_windows.insert(window.id);
+                        continue;
+                    }
+                    (window.id, window.layout.window_size)
+                }
+                Event::WindowLayoutsChanged { changes } => {
+                    match changes.into_iter().find(|(id, layout)| {
+                        undersized.contains(id) && big_enough(layout.window_size)
+                    }) {
+                        Some((id, layout)) => (id, layout.window_size),
+                        None => continue,
+                    }
+                }
+                Event::WindowClosed { id } => {
+                    undersized.retain(|u| *u != id);
+                    continue;
+                }
+                _ => continue,
+            };
+            if big_enough(size) {
+                self.seen_windows.insert(id);
+                return SpawnOutcome::Matched(id);
+            }
+            if !undersized.contains(&id) {
+                undersized.push(id);
+            }
+        }
+        if self.stream_closed {
+            SpawnOutcome::StreamClosed
+        } else {
+            SpawnOutcome::TimedOut
+        }
     }
-    pub fn foc_col_next_loop(self) -> Self {
-        self.send_action(json!({ "FocusColumnRightOrFirst": {} }));
+
+    /// Blocks until every app in `app_ids` has at least one open window or
+    /// `timeout` elapses. Without a timeout the connection's read timeout
+    /// applies to each event. Apps still without a window are then reported
+    /// by `missing_windows`.
+    pub fn wait_for_windows(mut self, app_ids: &[&str], timeout: Option<Duration>) -> Self {
+        self.require_event_stream("wait for windows");
+        let deadline = timeout.map(|t| Instant::now() + t);
+        let mut open: HashMap<u64, String> = self
+            .windows()
+            .expect("Failed to query windows")
+            .into_iter()
+            .filter_map(|w| Some((w.id, w.app_id?)))
+            .collect();
+        let missing = |open: &HashMap<u64, String>| -> Vec<String> {
+            app_ids
+                .iter()
+                .filter(|id| !open.values().any(|aid| aid == *id))
+                .map(|id| id.to_string())
+                .collect()
+        };
+
+        while !missing(&open).is_empty() {
+            match self.next_event_until(deadline) {
+                None => break,
+                Some(Event::WindowOpenedOrChanged { window }) => {
+                    self.seen_windows.insert(window.id);
+                    if let Some(aid) = window.app_id {
+                        open.insert(window.id, aid);
+                    }
+                }
+                Some(Event::WindowClosed { id }) => {
+                    open.remove(&id);
+                }
+                Some(_) => {}
+            }
+        }
+        self.missing_windows = missing(&open);
         self
     }
-    pub fn foc_col_prev_loop(self) -> Self {
-        self.send_action(json!({ "FocusColumnLeftOrLast": {} }));
-        self
+
+    /// Apps the last `wait_for_windows` call gave up waiting for.
+    pub fn missing_windows(&self) -> &[String] {
+        &self.missing_windows
     }
 
-    // Smart Focus (Combinations)
-    pub fn foc_win_mon_u(self) -> Self {
-        self.send_action(json!({ "FocusWindowOrMonitorUp": {} }));
+    /// Blocks until a window of `app_id` holds focus, or until `timeout`
+    /// elapses. Without a timeout the connection's read timeout applies.
+    pub fn wait_until_focused(mut self, app_id: &str, timeout: Option<Duration>) -> Self {
+        self.require_event_stream("wait for focus");
+        let deadline = timeout.map(|t| Instant::now() + t);
+        let windows = self.windows().expect("Failed to query windows");
+        if windows
+            .iter()
+            .any(|w| w.is_focused && w.app_id.as_deref() == Some(app_id))
+        {
+            return self;
+        }
+        let mut app_ids: HashMap<u64, String> = windows
+            .into_iter()
+            .filter_map(|w| Some((w.id, w.app_id?)))
+            .collect();
+
+        while let Some(event) = self.next_event_until(deadline) {
+            match event {
+                Event::WindowOpenedOrChanged { window } => {
+                    self.seen_windows.insert(window.id);
+                    if window.is_focused && window.app_id.as_deref() == Some(app_id) {
+                        break;
+                    }
+                    if let Some(aid) = window.app_id {
+                        app_ids.insert(window.id, aid);
+                    }
+                }
+                Event::WindowFocusChanged { id: Some(id) }
+                    if app_ids.get(&id).map(String::as_str) == Some(app_id) =>
+                {
+                    break;
+                }
+                _ => {}
+            }
+        }
         self
     }
-    pub fn foc_win_mon_d(self) -> Self {
-        self.send_action(json!({ "FocusWindowOrMonitorDown": {} }));
+
+    /// Blocks until the title of window `id` satisfies `predicate`, the window
+    /// closes, or `timeout` elapses. Without a timeout the connection's read
+    /// timeout applies.
+    pub fn wait_for_title<P>(mut self, id: u64, predicate: P, timeout: Option<Duration>) -> Self
+    where
+        P: Fn(&str) -> bool,
+    {
+        self.require_event_stream("wait for a title");
+        let deadline = timeout.map(|t| Instant::now() + t);
+        let matches = |w: &Window| w.id == id && w.title.as_deref().is_some_and(&predicate);
+        let windows = self.windows().expect("Failed to query windows");
+        if windows.iter().all(|w| w.id != id) || windows.iter().any(matches) {
+            return self;
+        }
+        while let Some(event) = self.next_event_until(deadline) {
+            match event {
+                Event::WindowOpenedOrChanged { window } if matches(&window) => break,
+                Event::WindowClosed { id: closed } if closed == id => break,
+                _ => {}
+            }
+        }
         self
     }
-    pub fn foc_col_mon_l(self) -> Self {
-        self.send_action(json!({ "FocusColumnOrMonitorLeft": {} }));
+
+    pub fn call<F>(mut self, func: F) -> Self
+    where
+        F: FnOnce(&mut Self),
+    {
+        func(&mut self);
         self
     }
-    pub fn foc_col_mon_r(self) -> Self {
-        self.send_action(json!({ "FocusColumnOrMonitorRight": {} }));
-        self
+
+    /// Runs `then` on the chain only if `cond` returns true. `cond` gets the
+    /// connection to query niri with, e.g.
+    /// `niri.when(|n| n.windows().is_ok_and(|w| w.len() > 3), |n| n.col_display(ColumnDisplay::Tabbed))`.
+    pub fn when<C, F>(mut self, cond: C, then: F) -> Self
+    where
+        C: FnOnce(&mut Self) -> bool,
+        F: FnOnce(Self) -> Self,
+    {
+        if cond(&mut self) { then(self) } else { self }
     }
-    pub fn foc_d_col_l(self) -> Self {
-        self.send_action(json!({ "FocusWindowDownOrColumnLeft": {} }));
+
+    /// Runs `func` on the chain `n` times, e.g. `niri.repeat(3, |n| n.foc_r())`.
+    pub fn repeat<F>(mut self, n: usize, func: F) -> Self
+    where
+        F: Fn(Self) -> Self,
+    {
+        for _ in 0..n {
+            self = func(self);
+        }
         self
     }
-    pub fn foc_d_col_r(self) -> Self {
-        self.send_action(json!({ "FocusWindowDownOrColumnRight": {} }));
-        self
+
+    /// Focuses window `id` for the duration of `func`, then gives focus back to
+    /// whichever window had it before. If nothing was focused, focus stays put.
+    pub fn with_focus<F>(mut self, id: u64, func: F) -> Self
+    where
+        F: FnOnce(&mut Self),
+    {
+        let previous = self
+            .focused_window()
+            .expect("Failed to query focused window");
+        self = self.foc_id(id);
+        func(&mut self);
+        match previous {
+            Some(w) => self.foc_id(w.id),
+            None => self,
+        }
     }
-    pub fn foc_u_col_l(self) -> Self {
-        self.send_action(json!({ "FocusWindowUpOrColumnLeft": {} }));
+
+    /// Keeps the session from going idle while `func` runs.
+    ///
+    /// niri has no idle-inhibit action, so this holds a logind idle lock by
+    /// running `systemd-inhibit --what=idle` for the duration. That stops idle
+    /// daemons which honour logind inhibitors, such as swayidle; ones that
+    /// only watch Wayland idle-inhibit surfaces will still fire. The lock is
+    /// released when `func` returns or panics. If `systemd-inhibit` can't be
+    /// started, `func` runs anyway after a warning on stderr.
+    pub fn idle_inhibit<F>(mut self, func: F) -> Self
+    where
+        F: FnOnce(&mut Self),
+    {
+        let _inhibitor = IdleInhibitor::start();
+        func(&mut self);
         self
     }
-    pub fn foc_u_col_r(self) -> Self {
-        self.send_action(json!({ "FocusWindowUpOrColumnRight": {} }));
+
+    /// Feeds every incoming event to `func` until it returns `Break` or the
+    /// event stream ends.
+    pub fn on_event<F>(mut self, mut func: F) -> Self
+    where
+        F: FnMut(&Event) -> ControlFlow<()>,
+    {
+        self.require_event_stream("watch events");
+        while let Some(event) = self.next_event() {
+            self.mark_seen(&event);
+            if func(&event).is_break() {
+                break;
+            }
+        }
         self
     }
-    pub fn foc_wspace_d(self) -> Self {
-        self.send_action(json!({ "FocusWindowOrWorkspaceDown": {} }));
-        self
+
+    /// Reads and applies every event niri has already sent, without waiting
+    /// for more, so that a later `spawn` or wait only sees what happens after
+    /// this call. On a replayed recording this drains the rest of the file.
+    pub fn drain_events(&mut self) {
+        let Some(reader) = &self.event_reader else {
+            return;
+        };
+        if reader.get_ref().set_nonblocking(true).is_err() {
+            return;
+        }
+        let mut guard = TimeoutGuard(self);
+        while let Ok(Some(line)) = guard.read_event_line() {
+            if let Ok(event) = serde_json::from_str(&line) {
+                guard.observe(&event);
+                guard.mark_seen(&event);
+            }
+        }
     }
-    pub fn foc_wspace_u(self) -> Self {
-        self.send_action(json!({ "FocusWindowOrWorkspaceUp": {} }));
-        self
+
+    /// Records the windows in `event` as already known, so `spawn` won't
+    /// mistake them for the window it is waiting for.
+    fn mark_seen(&mut self, event: &Event) {
+        match event {
+            Event::WindowsChanged { windows } => {
+                self.seen_windows.extend(windows.iter().map(|w| w.id));
+            }
+            Event::WindowOpenedOrChanged { window } => {
+                self.seen_windows.insert(window.id);
+            }
+            _ => {}
+        }
     }
 
-    // -------------------------------------------------------------------------
-    //  Window & Column Movement
-    // -------------------------------------------------------------------------
+    /// Calls `func` for every event that concerns window `id`, until the
+    /// window closes or the event stream ends.
+    pub fn watch_window<F>(&mut self, id: u64, mut func: F)
+    where
+        F: FnMut(&WindowEvent),
+    {
+        self.require_event_stream("watch a window");
+        let mut focused = self
+            .focused_window()
+            .expect("Failed to query focused window")
+            .is_some_and(|w| w.id == id);
+        while let Some(event) = self.next_event() {
+            let change = match event {
+                Event::WindowsChanged { windows } => {
+                    match windows.into_iter().find(|w| w.id == id) {
+                        Some(window) => WindowEvent::Changed(window),
+                        None => WindowEvent::Closed,
+                    }
+                }
+                Event::WindowOpenedOrChanged { window } if window.id == id => {
+                    focused = window.is_focused;
+                    WindowEvent::Changed(window)
+                }
+                Event::WindowClosed { id: closed } if closed == id => WindowEvent::Closed,
+                Event::WindowFocusChanged { id: now } if (now == Some(id)) != focused => {
+                    focused = !focused;
+                    if focused {
+                        WindowEvent::Focused
+                    } else {
+                        WindowEvent::Unfocused
+                    }
+                }
+                Event::WindowUrgencyChanged {
+                    id: urgent_id,
+                    urgent,
+                } if urgent_id == id => WindowEvent::UrgencyChanged(urgent),
+                Event::WindowLayoutsChanged { changes } => {
+                    match changes.into_iter().find(|(changed, _)| *changed == id) {
+                        Some((_, layout)) => WindowEvent::LayoutChanged(layout),
+                        None => continue,
+                    }
+                }
+                _ => continue,
+            };
+            func(&change);
+            if matches!(change, WindowEvent::Closed) {
+                break;
+            }
+        }
+    }
 
-    // Window Movement
-    pub fn mv_win_u(self) -> Self {
-        self.send_action(json!({ "MoveWindowUp": {} }));
-        self
+    /// Whether niri has closed the event stream. Once it has, `spawn` and the
+    /// other waiting methods return immediately.
+    pub fn stream_closed(&self) -> bool {
+        self.stream_closed
     }
-    pub fn mv_win_d(self) -> Self {
-        self.send_action(json!({ "MoveWindowDown": {} }));
-        self
+
+    pub fn events(&mut self) -> impl Iterator<Item = Event> + '_ {
+        std::iter::from_fn(move || self.next_event())
     }
-    pub fn mv_win_u_wspace(self) -> Self {
-        self.send_action(json!({ "MoveWindowUpOrToWorkspaceUp": {} }));
-        self
+
+    /// Like `events`, but only yields events of the given kinds. Other events
+    /// are skipped without being deserialized.
+    pub fn events_filtered<'a>(
+        &'a mut self,
+        kinds: &'a [EventKind],
+    ) -> impl Iterator<Item = Event> + 'a {
+        std::iter::from_fn(move || self.next_event_where(|kind| kinds.contains(&kind)))
     }
-    pub fn mv_win_d_wspace(self) -> Self {
-        self.send_action(json!({ "MoveWindowDownOrToWorkspaceDown": {} }));
-        self
+
+    /// Reads the next event, skipping lines that don't parse. Returns `None`
+    /// once the stream is closed or a read fails.
+    pub(crate) fn next_event(&mut self) -> Option<Event> {
+        self.next_event_where(|_| true)
     }
 
-    // Column Movement
-    pub fn mv_col_l(self) -> Self {
-        self.send_action(json!({ "MoveColumnLeft": {} }));
-        self
+    /// Like `next_event`, but skips events whose kind `wanted` rejects before
+    /// deserializing them.
+    fn next_event_where(&mut self, wanted: impl Fn(EventKind) -> bool) -> Option<Event> {
+        loop {
+            let line = match self.read_event_line() {
+                Ok(Some(line)) => line,
+                Ok(None) if self.reconnect && self.reopen_event_stream().is_ok() => continue,
+                _ => return None,
+            };
+            let kind = EventKind::of_line(&line);
+            let wanted = kind.is_none_or(&wanted);
+            // Kinds `observe` tracks state from are parsed even when unwanted.
+            if !wanted && !kind.is_some_and(EventKind::is_observed) {
+                continue;
+            }
+            if let Ok(event) = serde_json::from_str(&line) {
+                self.observe(&event);
+                if wanted {
+                    return Some(event);
+                }
+            }
+        }
     }
-    pub fn mv_col_r(self) -> Self {
-        self.send_action(json!({ "MoveColumnRight": {} }));
-        self
+
+    /// Updates client-side state from an event on its way to the caller.
+    fn observe(&mut self, event: &Event) {
+        match event {
+            Event::WindowsChanged { windows } if !self.synced => self.sync_from(windows),
+            Event::WindowFocusChanged { id: Some(id) } => self.record_focus(*id),
+            Event::WindowOpenedOrChanged { window } if window.is_focused => {
+                self.record_focus(window.id)
+            }
+            Event::WindowClosed { id } => self.focus_history.retain(|h| h != id),
+            _ => {}
+        }
     }
-    pub fn mv_col_first(self) -> Self {
-        self.send_action(json!({ "MoveColumnToFirst": {} }));
-        self
+
+    fn record_focus(&mut self, id: u64) {
+        if self.focus_history.back() == Some(&id) {
+            return;
+        }
+        if self.focus_history.len() == FOCUS_HISTORY_LEN {
+            self.focus_history.pop_front();
+        }
+        self.focus_history.push_back(id);
     }
-    pub fn mv_col_last(self) -> Self {
-        self.send_action(json!({ "MoveColumnToLast": {} }));
-        self
+
+    /// Like `next_event`, but gives up once `deadline` has passed. Without a
+    /// deadline, gives up after the connection's read timeout.
+    fn next_event_until(&mut self, deadline: Option<Instant>) -> Option<Event> {
+        let Some(deadline) = deadline.or_else(|| Some(Instant::now() + self.read_timeout?)) else {
+            return self.next_event();
+        };
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return None;
+        }
+        let source = self.event_reader.as_ref()?.get_ref();
+        source.set_read_timeout(Some(remaining)).ok()?;
+        let mut guard = TimeoutGuard(self);
+        guard.next_event()
     }
-    pub fn mv_col_idx(self, idx: usize) -> Self {
-        self.send_action(json!({ "MoveColumnToIndex": { "index": idx } }));
-        self
+
+    /// Reads one complete line from the event stream, or `None` at EOF or
+    /// without a stream. When a read times out mid-line the partial data is
+    /// kept for the next call.
+    fn read_event_line(&mut self) -> std::io::Result<Option<String>> {
+        let Some(reader) = &mut self.event_reader else {
+            return Ok(None);
+        };
+        match reader.read_line(&mut self.pending_line)? {
+            0 => {
+                self.stream_closed = true;
+                Ok(None)
+            }
+            _ => {
+                let line = std::mem::take(&mut self.pending_line);
+                self.record_line(&line);
+                Ok(Some(line))
+            }
+        }
     }
-    pub fn mv_col_l_mon(self) -> Self {
-        self.send_action(json!({ "MoveColumnLeftOrToMonitorLeft": {} }));
+
+    /// Appends every event line read from now on to `path`, as JSON lines.
+    /// The file starts with a `WindowsChanged` snapshot of the current
+    /// windows, so it reads like a fresh event stream. Each line is flushed
+    /// as it is written; recording stops quietly if a write fails.
+    pub fn record_events(mut self, path: &Path) -> Self {
+        create_parent_dir(path.to_str());
+        let file = File::create(path)
+            .unwrap_or_else(|e| panic!("Failed to create {}: {e}", path.display()));
+        let windows = self.windows().expect("Failed to query windows");
+        let snapshot =
+            serde_json::to_string(&Event::WindowsChanged { windows }).expect("events serialize");
+        self.recording = Some(file);
+        self.record_line(&format!("{snapshot}\n"));
         self
     }
-    pub fn mv_col_r_mon(self) -> Self {
-        self.send_action(json!({ "MoveColumnRightOrToMonitorRight": {} }));
-        self
+
+    fn record_line(&mut self, line: &str) {
+        if let Some(file) = &mut self.recording
+            && file
+                .write_all(line.as_bytes())
+                .and_then(|_| file.flush())
+                .is_err()
+        {
+            self.recording = None;
+        }
     }
 
     // -------------------------------------------------------------------------
-    //  Layout Manipulation & Sizing
+    //  Queries
     // -------------------------------------------------------------------------
 
-    // Consumption / Expulsion / Swapping
-    pub fn consume(self) -> Self {
-        self.send_action(json!({ "ConsumeWindowIntoColumn": {} }));
-        self
-    }
-    pub fn consume_expel_l(self, id: Option<u64>) -> Self {
-        self.send_action(json!({ "ConsumeOrExpelWindowLeft": { "id": id } }));
-        self
-    }
-    pub fn consume_expel_r(self, id: Option<u64>) -> Self {
-        self.send_action(json!({ "ConsumeOrExpelWindowRight": { "id": id } }));
-        self
-    }
-    pub fn expel(self) -> Self {
-        self.send_action(json!({ "ExpelWindowFromColumn": {} }));
-        self
-    }
-    pub fn swap_l(self) -> Self {
-        self.send_action(json!({ "SwapWindowLeft": {} }));
-        self
-    }
-    pub fn swap_r(self) -> Self {
-        self.send_action(json!({ "SwapWindowRight": {} }));
-        self
+    /// Cheap liveness probe: succeeds only if niri answers a version request.
+    pub fn ping(&mut self) -> Result<(), Error> {
+        match self.request(Request::Version)? {
+            Response::Version(_) => Ok(()),
+            _ => Err(Error::UnexpectedResponse),
+        }
     }
 
-    // Layout Display
-    pub fn layout_switch(self, target: LayoutSwitchTarget) -> Self {
-        self.send_action(json!({ "SwitchLayout": { "layout": target } }));
-        self
-    }
-    pub fn toggle_tab(self) -> Self {
-        self.send_action(json!({ "ToggleColumnTabbedDisplay": {} }));
-        self
-    }
-    pub fn col_display(self, mode: ColumnDisplay) -> Self {
-        self.send_action(json!({ "SetColumnDisplay": { "display": mode } }));
-        self
+    /// Whether the window-rule opacity of window `id` is currently active.
+    ///
+    /// niri's window info doesn't include this, so it always returns
+    /// `Error::Unsupported`.
+    pub fn window_opacity_state(&mut self, _id: u64) -> Result<bool, Error> {
+        Err(Error::Unsupported)
     }
 
-    // Centering
-    pub fn center_col(self) -> Self {
-        self.send_action(json!({ "CenterColumn": {} }));
-        self
-    }
-    pub fn center_win(self, id: Option<u64>) -> Self {
-        self.send_action(json!({ "CenterWindow": { "id": id } }));
-        self
-    }
-    pub fn center_vis_cols(self) -> Self {
-        self.send_action(json!({ "CenterVisibleColumns": {} }));
-        self
+    pub fn overview_is_open(&mut self) -> Result<bool, Error> {
+        match self.request(Request::OverviewState)? {
+            Response::OverviewState(overview) => Ok(overview.is_open),
+            _ => Err(Error::UnexpectedResponse),
+        }
     }
 
-    // Dimensions (Columns)
-    pub fn col_width(self, val: f64) -> Self {
-        self.send_action(json!({
-            "SetColumnWidth": { "change": { "SetProportion": val } }
-        }));
-        self
+    pub fn windows(&mut self) -> Result<Vec<Window>, Error> {
+        match self.request(Request::Windows)? {
+            Response::Windows(windows) => Ok(windows),
+            _ => Err(Error::UnexpectedResponse),
+        }
     }
-    pub fn col_max(self) -> Self {
-        self.send_action(json!({ "MaximizeColumn": {} }));
-        self
+
+    pub fn focused_window(&mut self) -> Result<Option<Window>, Error> {
+        match self.request(Request::FocusedWindow)? {
+            Response::FocusedWindow(window) => Ok(window),
+            _ => Err(Error::UnexpectedResponse),
+        }
     }
-    pub fn expand_col(self) -> Self {
-        self.send_action(json!({ "ExpandColumnToAvailableWidth": {} }));
-        self
+
+    /// Window `id`, or the focused window for `None`.
+    fn window_or_focused(&mut self, id: Option<u64>) -> Option<Window> {
+        self.windows()
+            .expect("Failed to query windows")
+            .into_iter()
+            .find(|w| match id {
+                Some(id) => w.id == id,
+                None => w.is_focused,
+            })
     }
-    pub fn preset_col_width(self) -> Self {
-        self.send_action(json!({ "SwitchPresetColumnWidth": {} }));
-        self
+
+    /// The most recently focused window of `app_id`, if it has any.
+    fn latest_window_of(&mut self, app_id: &str) -> Option<Window> {
+        self.windows()
+            .expect("Failed to query windows")
+            .into_iter()
+            .filter(|w| w.app_id.as_deref() == Some(app_id))
+            .max_by_key(|w| w.focus_timestamp.map(|t| (t.secs, t.nanos)))
     }
-    pub fn preset_col_width_back(self) -> Self {
-        self.send_action(json!({ "SwitchPresetColumnWidthBack": {} }));
-        self
+
+    /// The id of the workspace `r` refers to. Indices count on the focused
+    /// output, as they do in niri's own actions.
+    fn resolve_wspace(&mut self, r: &WorkspaceReferenceArg) -> Option<u64> {
+        let workspaces = self.workspaces().expect("Failed to query workspaces");
+        find_wspace(&workspaces, r)
     }
 
-    // Dimensions (Windows)
-    pub fn win_width(self, id: Option<u64>, c: SizeChange) -> Self {
-        self.send_action(json!({ "SetWindowWidth": { "id": id, "change": c } }));
-        self
+    /// The workspace window `id` is on, ready to pass to `foc_wspace` and
+    /// friends. `None` if the window is gone or not on a workspace.
+    pub fn window_workspace(&mut self, id: u64) -> Result<Option<WorkspaceReferenceArg>, Error> {
+        Ok(self
+            .windows()?
+            .into_iter()
+            .find(|w| w.id == id)
+            .and_then(|w| w.workspace_id)
+            .map(WorkspaceReferenceArg::Id))
     }
-    pub fn win_height(self, val: f64) -> Self {
-        self.send_action(json!({
-            "SetWindowHeight": { "change": { "SetProportion": val } }
-        }));
-        self
+
+    pub fn is_running(&mut self, app_id: &str) -> Result<bool, Error> {
+        Ok(self
+            .windows()?
+            .iter()
+            .any(|w| w.app_id.as_deref() == Some(app_id)))
     }
-    pub fn reset_win_height(self, id: Option<u64>) -> Self {
-        self.send_action(json!({ "ResetWindowHeight": { "id": id } }));
-        self
+
+    pub fn workspaces(&mut self) -> Result<Vec<Workspace>, Error> {
+        match self.request(Request::Workspaces)? {
+            Response::Workspaces(workspaces) => Ok(workspaces),
+            _ => Err(Error::UnexpectedResponse),
+        }
     }
-    pub fn max_win_edge(self, id: Option<u64>) -> Self {
-        self.send_action(json!({ "MaximizeWindowToEdges": { "id": id } }));
-        self
+
+    /// The active workspace of each output, keyed by output name.
+    pub fn active_workspaces(&mut self) -> Result<HashMap<String, Workspace>, Error> {
+        Ok(self
+            .workspaces()?
+            .into_iter()
+            .filter(|w| w.is_active)
+            .filter_map(|w| Some((w.output.clone()?, w)))
+            .collect())
     }
-    pub fn preset_win_width(self, id: Option<u64>) -> Self {
-        self.send_action(json!({ "SwitchPresetWindowWidth": { "id": id } }));
-        self
+
+    /// How many windows are on workspace `r`, or on the focused workspace for
+    /// `None`. Floating windows count. A workspace that doesn't exist has none.
+    pub fn window_count(&mut self, r: Option<WorkspaceReferenceArg>) -> Result<usize, Error> {
+        let workspaces = self.workspaces()?;
+        let workspace = match r {
+            Some(r) => find_wspace(&workspaces, &r),
+            None => workspaces.iter().find(|w| w.is_focused).map(|w| w.id),
+        };
+        let Some(workspace) = workspace else {
+            return Ok(0);
+        };
+        Ok(self
+            .windows()?
+            .iter()
+            .filter(|w| w.workspace_id == Some(workspace))
+            .count())
     }
-    pub fn preset_win_width_back(self, id: Option<u64>) -> Self {
-        self.send_action(json!({ "SwitchPresetWindowWidthBack": { "id": id } }));
-        self
+
+    pub fn focused_output(&mut self) -> Result<Option<Output>, Error> {
+        match self.request(Request::FocusedOutput)? {
+            Response::FocusedOutput(output) => Ok(output),
+            _ => Err(Error::UnexpectedResponse),
+        }
     }
-    pub fn preset_win_height(self, id: Option<u64>) -> Self {
-        self.send_action(json!({ "SwitchPresetWindowHeight": { "id": id } }));
-        self
+
+    pub fn outputs(&mut self) -> Result<Vec<Output>, Error> {
+        match self.request(Request::Outputs)? {
+            Response::Outputs(outputs) => Ok(outputs.into_values().collect()),
+            _ => Err(Error::UnexpectedResponse),
+        }
     }
-    pub fn preset_win_height_back(self, id: Option<u64>) -> Self {
-        self.send_action(json!({ "SwitchPresetWindowHeightBack": { "id": id } }));
-        self
+
+    /// The columns of the focused workspace, left to right. Floating windows
+    /// aren't part of any column. Empty if no workspace is focused.
+    pub fn columns(&mut self) -> Result<Vec<Column>, Error> {
+        let focused = self.workspaces()?.into_iter().find(|w| w.is_focused);
+        let Some(workspace) = focused else {
+            return Ok(Vec::new());
+        };
+        Ok(column::group(&self.windows()?, workspace.id))
     }
 
     // -------------------------------------------------------------------------
-    //  Window State (Close, Fullscreen, Float, Urgent)
+    //  Compound Actions
     // -------------------------------------------------------------------------
+    //
+    // These look at niri's state, or this connection's, before acting.
 
-    pub fn close(self, id: Option<u64>) -> Self {
-        self.send_action(json!({ "CloseWindow": { "id": id } }));
-        self
+    // Focus
+    /// Focuses a window of `app_id`. With several matches, the most recently
+    /// focused one wins. Does nothing if the app has no windows.
+    pub fn foc_app(mut self, app_id: &str) -> Self {
+        let target = self.latest_window_of(app_id);
+        match target {
+            Some(w) => self.foc_id(w.id),
+            None => self,
+        }
     }
 
-    pub fn fullscreen(self, id: Option<u64>) -> Self {
-        self.send_action(json!({ "FullscreenWindow": { "id": id } }));
-        self
+    /// Focuses `app` if it has a window, the most recently focused one if it
+    /// has several, and spawns it otherwise.
+    pub fn focus_or_spawn(mut self, app: &impl Spawnable) -> Self {
+        if self
+            .is_running(app.app_id())
+            .expect("Failed to query windows")
+        {
+            self.foc_app(app.app_id())
+        } else {
+            self.spawn(app, None)
+        }
     }
 
-    pub fn fake_fullscreen(self, id: Option<u64>) -> Self {
-        self.send_action(json!({ "ToggleWindowedFullscreen": { "id": id } }));
-        self
+    /// Focuses an urgent window, the most recently focused one if several
+    /// are. Does nothing if no window is urgent.
+    pub fn foc_urgent(mut self) -> Self {
+        let target = self
+            .windows()
+            .expect("Failed to query windows")
+            .into_iter()
+            .filter(|w| w.is_urgent)
+            .max_by_key(|w| w.focus_timestamp.map(|t| (t.secs, t.nanos)));
+        match target {
+            Some(w) => self.foc_id(w.id),
+            None => self,
+        }
     }
 
-    pub fn opacity_toggle(self, id: Option<u64>) -> Self {
-        self.send_action(json!({ "ToggleWindowRuleOpacity": { "id": id } }));
-        self
+    /// Focuses the window that had focus `n` changes ago, so `focus_back(1)`
+    /// behaves like alt-tab. The history is built from events this
+    /// connection has read, and windows that have since closed are skipped.
+    /// Does nothing if the history doesn't reach back that far.
+    pub fn focus_back(mut self, n: usize) -> Self {
+        let open: HashSet<u64> = self
+            .windows()
+            .expect("Failed to query windows")
+            .into_iter()
+            .map(|w| w.id)
+            .collect();
+        let mut live = self
+            .focus_history
+            .iter()
+            .rev()
+            .filter(|id| open.contains(id));
+        let target = live.nth(n).copied();
+        match target {
+            Some(id) => self.foc_id(id),
+            None => self,
+        }
     }
 
-    // Floating / Tiling
-    pub fn float_toggle(self, id: Option<u64>) -> Self {
-        self.send_action(json!({ "ToggleWindowFloating": { "id": id } }));
-        self
-    }
-    pub fn mv_float(self, id: Option<u64>) -> Self {
-        self.send_action(json!({ "MoveWindowToFloating": { "id": id } }));
-        self
-    }
-    pub fn mv_tile(self, id: Option<u64>) -> Self {
-        self.send_action(json!({ "MoveWindowToTiling": { "id": id } }));
-        self
-    }
-    pub fn foc_float(self) -> Self {
-        self.send_action(json!({ "FocusFloating": {} }));
-        self
-    }
-    pub fn foc_tile(self) -> Self {
-        self.send_action(json!({ "FocusTiling": {} }));
-        self
-    }
-    pub fn foc_float_tile_switch(self) -> Self {
-        self.send_action(json!({ "SwitchFocusBetweenFloatingAndTiling": {} }));
-        self
+    /// Focuses the leftmost column on the focused workspace that holds a
+    /// window of `app_id`. Does nothing if there is none.
+    pub fn foc_col_app(mut self, app_id: &str) -> Self {
+        let workspace = self
+            .workspaces()
+            .expect("Failed to query workspaces")
+            .into_iter()
+            .find(|w| w.is_focused)
+            .map(|w| w.id);
+        let column = self
+            .windows()
+            .expect("Failed to query windows")
+            .into_iter()
+            .filter(|w| w.workspace_id == workspace && w.app_id.as_deref() == Some(app_id))
+            .filter_map(|w| w.layout.pos_in_scrolling_layout.map(|(col, _)| col))
+            .min();
+        match column {
+            Some(col) => self.foc_col_idx(col),
+            None => self,
+        }
     }
-    pub fn mv_float_win(self, id: Option<u64>, x: PositionChange, y: PositionChange) -> Self {
-        self.send_action(json!({ "MoveFloatingWindow": { "id": id, "x": x, "y": y } }));
-        self
+
+    /// Focuses the workspace window `id` is on, then the window itself, so
+    /// the jump is the same wherever the window is. Does nothing if the
+    /// window is gone.
+    pub fn foc_window_and_wspace(mut self, id: u64) -> Self {
+        match self.window_workspace(id).expect("Failed to query windows") {
+            Some(workspace) => self.foc_wspace(workspace).foc_id(id),
+            None => self,
+        }
     }
 
-    // Urgency
-    pub fn urgent_toggle(self, id: u64) -> Self {
-        self.send_action(json!({ "ToggleWindowUrgent": { "id": id } }));
-        self
+    /// Focuses the window on the focused workspace with the largest area.
+    /// Among windows of equal area, the one in the leftmost column wins, then
+    /// the topmost in that column; floating windows lose ties to tiled ones.
+    pub fn foc_largest(self) -> Self {
+        self.foc_by_area(true)
     }
-    pub fn urgent_set(self, id: u64) -> Self {
-        self.send_action(json!({ "SetWindowUrgent": { "id": id } }));
-        self
+
+    /// Like `foc_largest`, for the window with the smallest area.
+    pub fn foc_smallest(self) -> Self {
+        self.foc_by_area(false)
     }
-    pub fn urgent_unset(self, id: u64) -> Self {
-        self.send_action(json!({ "UnsetWindowUrgent": { "id": id } }));
-        self
+
+    fn foc_by_area(mut self, largest: bool) -> Self {
+        let workspace = self
+            .workspaces()
+            .expect("Failed to query workspaces")
+            .into_iter()
+            .find(|w| w.is_focused)
+            .map(|w| w.id);
+        let target = self
+            .windows()
+            .expect("Failed to query windows")
+            .into_iter()
+            .filter(|w| workspace.is_some() && w.workspace_id == workspace)
+            .min_by_key(|w| {
+                let (width, height) = w.layout.window_size;
+                let area = i64::from(width) * i64::from(height);
+                let pos = w
+                    .layout
+                    .pos_in_scrolling_layout
+                    .unwrap_or((usize::MAX, usize::MAX));
+                (if largest { -area } else { area }, pos)
+            });
+        match target {
+            Some(w) => self.foc_id(w.id),
+            None => self,
+        }
     }
 
-    // -------------------------------------------------------------------------
-    //  Workspace Management
-    // -------------------------------------------------------------------------
+    // Consumption
+    /// Stacks the most recently focused window of each app in `app_ids` into
+    /// one column, top to bottom, where the first app's column is. Each window
+    /// has its column moved next to that one and is then consumed into it, so
+    /// each should be alone in its column to begin with. Apps without a
+    /// window on the first app's workspace are skipped. Focus
+    /// ends on the first app's window.
+    pub fn build_column(mut self, app_ids: &[&str]) -> Self {
+        let Some((first, rest)) = app_ids.split_first() else {
+            return self;
+        };
+        let Some(anchor) = self.latest_window_of(first) else {
+            return self;
+        };
+        let column = |w: &Window| w.layout.pos_in_scrolling_layout.map(|(col, _)| col);
+        for app_id in rest {
+            let windows = self.windows().expect("Failed to query windows");
+            let Some(target) = windows.iter().find(|w| w.id == anchor.id).and_then(column) else {
+                break;
+            };
+            let next = windows
+                .iter()
+                .filter(|w| {
+                    w.app_id.as_deref() == Some(*app_id)
+                        && w.workspace_id == anchor.workspace_id
+                        && column(w).is_some_and(|col| col != target)
+                })
+                .max_by_key(|w| w.focus_timestamp.map(|t| (t.secs, t.nanos)));
+            let Some((id, col)) = next.and_then(|w| Some((w.id, column(w)?))) else {
+                continue;
+            };
+            let dest = if col > target { target + 1 } else { target };
+            self = self.foc_id(id).mv_col_idx(dest).consume_expel_l(Some(id));
+        }
+        self.foc_id(anchor.id)
+    }
 
-    // Workspace Focus/Switch
-    pub fn foc_wspace(self, r: WorkspaceReferenceArg) -> Self {
-        self.send_action(json!({ "FocusWorkspace": { "reference": r } }));
-        self
+    /// Swaps the places of windows `a` and `b` on the same workspace, leaving
+    /// `a` focused. Within one column the windows are moved up and down past
+    /// each other. Across columns their whole columns are swapped instead, so
+    /// any other windows in those columns move along. Floating windows and
+    /// windows on different workspaces are left alone.
+    pub fn swap_windows(mut self, a: u64, b: u64) -> Self {
+        let windows = self.windows().expect("Failed to query windows");
+        let find = |id: u64| windows.iter().find(|w| w.id == id);
+        let (Some(wa), Some(wb)) = (find(a), find(b)) else {
+            return self;
+        };
+        let (Some((col_a, row_a)), Some((col_b, row_b))) = (
+            wa.layout.pos_in_scrolling_layout,
+            wb.layout.pos_in_scrolling_layout,
+        ) else {
+            return self;
+        };
+        if a == b || wa.workspace_id != wb.workspace_id {
+            return self;
+        }
+        if col_a != col_b {
+            return self
+                .foc_id(a)
+                .mv_col_idx(col_b)
+                .foc_id(b)
+                .mv_col_idx(col_a)
+                .foc_id(a);
+        }
+        // Move the upper window down into the lower one's row, which shifts
+        // the lower one up by one, then move that one up to the upper row.
+        let ((upper, top), (lower, bottom)) = if row_a < row_b {
+            ((a, row_a), (b, row_b))
+        } else {
+            ((b, row_b), (a, row_a))
+        };
+        self = self.foc_id(upper);
+        for _ in top..bottom {
+            self = self.mv_win_d();
+        }
+        self = self.foc_id(lower);
+        for _ in top..bottom - 1 {
+            self = self.mv_win_u();
+        }
+        self.foc_id(a)
     }
-    pub fn foc_wspace_prev(self) -> Self {
-        self.send_action(json!({ "FocusWorkspacePrevious": {} }));
-        self
+
+    // Column Widths
+    /// Gives every column on the focused workspace the same share of the
+    /// screen width. Focus returns to the window focused before.
+    ///
+    /// niri doesn't report whether a column was given a fixed width, so every
+    /// column is counted. Use `balance_columns_except` to leave some out.
+    pub fn balance_columns(self) -> Self {
+        self.balance_columns_except(&[])
     }
-    pub fn wspace_d(self) -> Self {
-        self.send_action(json!({ "FocusWorkspaceDown": {} }));
-        self
+
+    /// Like `balance_columns`, but columns holding any window in `fixed` keep
+    /// their width and are left out of the split.
+    pub fn balance_columns_except(mut self, fixed: &[u64]) -> Self {
+        let columns: Vec<Column> = self
+            .columns()
+            .expect("Failed to query columns")
+            .into_iter()
+            .filter(|c| !c.windows.iter().any(|id| fixed.contains(id)))
+            .collect();
+        if columns.is_empty() {
+            return self;
+        }
+        let previous = self
+            .focused_window()
+            .expect("Failed to query focused window");
+        let share = 1.0 / columns.len() as f64;
+        for column in &columns {
+            self = self.foc_id(column.windows[0]).col_width(share);
+        }
+        match previous {
+            Some(w) => self.foc_id(w.id),
+            None => self,
+        }
     }
-    pub fn wspace_u(self) -> Self {
-        self.send_action(json!({ "FocusWorkspaceUp": {} }));
-        self
+
+    // Layout Display
+    /// Switches the focused column to the next display mode, wrapping around.
+    ///
+    /// niri doesn't report a column's display mode, so this steps on from the
+    /// mode it last set, starting from `Normal`. Modes set any other way,
+    /// or on other columns, aren't taken into account.
+    pub fn col_display_cycle(mut self) -> Self {
+        self.column_display = match self.column_display {
+            ColumnDisplay::Normal => ColumnDisplay::Tabbed,
+            ColumnDisplay::Tabbed => ColumnDisplay::Normal,
+        };
+        let mode = self.column_display;
+        self.col_display(mode)
     }
 
-    // Workspace Movement (Reordering)
-    pub fn mv_wspace_d(self) -> Self {
-        self.send_action(json!({ "MoveWorkspaceDown": {} }));
-        self
+    // Centering
+    /// Centers a window of `app_id`, preferring the most recently focused one
+    /// when there are several. Does nothing if the app has no windows.
+    pub fn center_app(mut self, app_id: &str) -> Self {
+        let target = self.latest_window_of(app_id);
+        match target {
+            Some(w) => self.center_win(Some(w.id)),
+            None => self,
+        }
     }
-    pub fn mv_wspace_u(self) -> Self {
-        self.send_action(json!({ "MoveWorkspaceUp": {} }));
-        self
+
+    // Floating
+    /// Moves a floating window (`None` for the focused one) to `anchor` on the
+    /// focused output, `margin` logical pixels in from the edges it touches.
+    /// Positions are worked out from the output's full logical size, so bars
+    /// and other exclusive zones are not accounted for.
+    pub fn float_anchor(mut self, id: Option<u64>, anchor: Anchor, margin: f64) -> Self {
+        let area = self
+            .focused_output()
+            .expect("Failed to query focused output")
+            .and_then(|o| o.logical)
+            .map(|l| (f64::from(l.width), f64::from(l.height)));
+        let window = self.window_or_focused(id);
+        let (Some(area), Some(window)) = (area, window) else {
+            return self;
+        };
+        let (x, y) = anchor.position(area, window.layout.tile_size, margin);
+        self.mv_float_win(
+            Some(window.id),
+            PositionChange::SetFixed(x),
+            PositionChange::SetFixed(y),
+        )
     }
-    pub fn mv_wspace_idx(self, idx: usize, r: Option<WorkspaceReferenceArg>) -> Self {
-        self.send_action(json!({ "MoveWorkspaceToIndex": { "index": idx, "reference": r } }));
-        self
+
+    /// Makes a window (`None` for the focused one) floating or tiled, sending
+    /// nothing if it already is.
+    pub fn set_floating(mut self, id: Option<u64>, floating: bool) -> Self {
+        let window = self.window_or_focused(id);
+        match window {
+            Some(w) if w.is_floating != floating => {
+                if floating {
+                    self.mv_float(Some(w.id))
+                } else {
+                    self.mv_tile(Some(w.id))
+                }
+            }
+            _ => self,
+        }
     }
 
-    // Moving Content to Workspaces
-    pub fn mv_win_wspace(self, id: Option<u64>, r: WorkspaceReferenceArg, focus: bool) -> Self {
-        self.send_action(json!({
-            "MoveWindowToWorkspace": { "window_id": id, "reference": r, "focus": focus }
-        }));
-        self
+    // Window State
+    /// Fullscreens window `id` for the duration of `func`, then takes it out
+    /// of fullscreen and sets its width and height back to what they were.
+    /// Does nothing if the window doesn't exist.
+    pub fn fullscreen_scoped<F>(mut self, id: u64, func: F) -> Self
+    where
+        F: FnOnce(&mut Self),
+    {
+        let Some(before) = self.window_or_focused(Some(id)) else {
+            return self;
+        };
+        let (width, height) = before.layout.window_size;
+        self = self.fullscreen(Some(id));
+        func(&mut self);
+        self = self.fullscreen(Some(id));
+        self.win_width_px(Some(id), width)
+            .win_height_px(Some(id), height)
     }
-    pub fn mv_win_wspace_d(self, focus: bool) -> Self {
-        self.send_action(json!({ "MoveWindowToWorkspaceDown": { "focus": focus } }));
+
+    pub fn close_all(mut self, app_id: &str) -> Self {
+        let ids: Vec<u64> = self
+            .windows()
+            .expect("Failed to query windows")
+            .into_iter()
+            .filter(|w| w.app_id.as_deref() == Some(app_id))
+            .map(|w| w.id)
+            .collect();
+        for id in ids {
+            self = self.close(Some(id));
+        }
         self
     }
-    pub fn mv_win_wspace_u(self, focus: bool) -> Self {
-        self.send_action(json!({ "MoveWindowToWorkspaceUp": { "focus": focus } }));
-        self
+
+    // Snapshots
+    /// Captures every window's app_id, title, workspace, floating state and
+    /// size, for `restore` to put back later.
+    pub fn snapshot(&mut self) -> Result<LayoutSnapshot, Error> {
+        let workspaces = self.workspaces()?;
+        Ok(LayoutSnapshot::capture(&self.windows()?, &workspaces))
     }
-    pub fn mv_col_wspace(self, r: WorkspaceReferenceArg, focus: bool) -> Self {
-        self.send_action(json!({ "MoveColumnToWorkspace": { "reference": r, "focus": focus } }));
+
+    /// Moves windows back to the workspaces, floating state and sizes in
+    /// `snap`, as closely as niri allows.
+    ///
+    /// Window ids don't survive an app restarting, so windows are matched by
+    /// app_id and title, falling back to app_id alone; windows without an
+    /// app_id, and windows with no match, are left alone. A workspace is found
+    /// again by id, or by name if it was recreated, and the window stays put
+    /// if neither exists any more. Column order, floating positions and
+    /// fullscreen state aren't restored.
+    pub fn restore(mut self, snap: &LayoutSnapshot) -> Self {
+        let current = self.windows().expect("Failed to query windows");
+        let workspaces = self.workspaces().expect("Failed to query workspaces");
+        for (saved, id) in snap.match_windows(&current) {
+            let workspace = match (saved.workspace_id, &saved.workspace_name) {
+                (Some(ws), _) if workspaces.iter().any(|w| w.id == ws) => {
+                    Some(WorkspaceReferenceArg::Id(ws))
+                }
+                (_, Some(name)) if workspaces.iter().any(|w| w.name.as_ref() == Some(name)) => {
+                    Some(WorkspaceReferenceArg::Name(name.clone()))
+                }
+                _ => None,
+            };
+            if let Some(workspace) = workspace {
+                self = self.mv_win_wspace(Some(id), workspace, false);
+            }
+            let (width, height) = saved.size;
+            self = self
+                .set_floating(Some(id), saved.is_floating)
+                .win_width_px(Some(id), width)
+                .win_height_px(Some(id), height);
+        }
         self
     }
-    pub fn mv_col_wspace_d(self, focus: bool) -> Self {
-        self.send_action(json!({ "MoveColumnToWorkspaceDown": { "focus": focus } }));
-        self
+
+    // Workspaces
+    /// Focuses the workspace called `name`, creating it first if it doesn't exist.
+    ///
+    /// niri keeps an empty workspace at the end of each output, so a missing
+    /// workspace is created by naming the empty one on the focused output.
+    pub fn ensure_wspace(mut self, name: &str) -> Self {
+        let workspaces = self.workspaces().expect("Failed to query workspaces");
+        if !workspaces.iter().any(|w| w.name.as_deref() == Some(name)) {
+            let output = workspaces
+                .iter()
+                .find(|w| w.is_focused)
+                .and_then(|w| w.output.clone());
+            let empty = workspaces
+                .iter()
+                .filter(|w| w.output == output && w.active_window_id.is_none())
+                .max_by_key(|w| w.idx);
+            if let Some(empty) = empty {
+                self =
+                    self.name_wspace(name.to_string(), Some(WorkspaceReferenceArg::Id(empty.id)));
+            }
+        }
+        self.foc_wspace_name(name)
     }
-    pub fn mv_col_wspace_u(self, focus: bool) -> Self {
-        self.send_action(json!({ "MoveColumnToWorkspaceUp": { "focus": focus } }));
-        self
+
+    /// Focuses whichever of the workspaces `a` and `b` isn't focused now,
+    /// or `a` if neither is.
+    pub fn toggle_wspace(mut self, a: &str, b: &str) -> Self {
+        let focused = self
+            .workspaces()
+            .expect("Failed to query workspaces")
+            .into_iter()
+            .find(|w| w.is_focused)
+            .and_then(|w| w.name);
+        if focused.as_deref() == Some(a) {
+            self.foc_wspace_name(b)
+        } else {
+            self.foc_wspace_name(a)
+        }
     }
 
-    // Naming
-    pub fn name_wspace(self, name: String, r: Option<WorkspaceReferenceArg>) -> Self {
-        self.send_action(json!({ "SetWorkspaceName": { "name": name, "workspace": r } }));
-        self
+    /// Moves a window (`None` for the focused one) to another workspace, taking
+    /// its whole column along if it shares that column with other windows.
+    /// Columns can only be moved while focused, so the window is focused first
+    /// in that case.
+    pub fn mv_smart_wspace(
+        mut self,
+        id: Option<u64>,
+        r: WorkspaceReferenceArg,
+        focus: bool,
+    ) -> Self {
+        let windows = self.windows().expect("Failed to query windows");
+        let Some(target) = windows.iter().find(|w| match id {
+            Some(id) => w.id == id,
+            None => w.is_focused,
+        }) else {
+            return self;
+        };
+        let column = |w: &Window| w.layout.pos_in_scrolling_layout.map(|(col, _)| col);
+        let shares_column = column(target).is_some()
+            && windows.iter().any(|w| {
+                w.id != target.id
+                    && w.workspace_id == target.workspace_id
+                    && column(w) == column(target)
+            });
+        if !shares_column {
+            return self.mv_win_wspace(Some(target.id), r, focus);
+        }
+        if !target.is_focused {
+            self = self.foc_id(target.id);
+        }
+        self.mv_col_wspace(r, focus)
     }
-    pub fn unname_wspace(self, r: Option<WorkspaceReferenceArg>) -> Self {
-        self.send_action(json!({ "UnsetWorkspaceName": { "reference": r } }));
-        self
+
+    /// Moves a window (`None` for the focused one) to a workspace on `output`.
+    /// An index counts on `output` rather than on the focused output.
+    ///
+    /// The workspace is resolved to its id first and the window moved there in
+    /// a single action, so it never lands on an intermediate workspace. If no
+    /// such workspace exists on `output`, including an id or name that refers
+    /// to a workspace elsewhere, the window is only moved to `output`.
+    pub fn mv_win_wspace_on_mon(
+        mut self,
+        id: Option<u64>,
+        workspace: WorkspaceReferenceArg,
+        output: &str,
+        focus: bool,
+    ) -> Self {
+        let target = self
+            .workspaces()
+            .expect("Failed to query workspaces")
+            .into_iter()
+            .filter(|w| w.output.as_deref() == Some(output))
+            .find(|w| match &workspace {
+                WorkspaceReferenceArg::Id(id) => w.id == *id,
+                WorkspaceReferenceArg::Index(idx) => w.idx == *idx,
+                WorkspaceReferenceArg::Name(name) => w.name.as_deref() == Some(name),
+            });
+        match target {
+            Some(w) => self.mv_win_wspace(id, WorkspaceReferenceArg::Id(w.id), focus),
+            None => self.mv_win_mon(id, output.to_string()),
+        }
     }
 
-    // -------------------------------------------------------------------------
-    //  Monitor Management
-    // -------------------------------------------------------------------------
+    /// Moves a window (`None` for the focused one) `offset` workspaces down,
+    /// or up for a negative offset, stopping at the first or last workspace.
+    /// Only workspaces on the window's own output count, so it never crosses
+    /// to another monitor.
+    pub fn mv_win_wspace_rel(mut self, id: Option<u64>, offset: i32, focus: bool) -> Self {
+        let Some(window) = self.window_or_focused(id) else {
+            return self;
+        };
+        let workspaces = self.workspaces().expect("Failed to query workspaces");
+        let Some(current) = workspaces
+            .iter()
+            .find(|w| Some(w.id) == window.workspace_id)
+        else {
+            return self;
+        };
+        let mut siblings: Vec<&Workspace> = workspaces
+            .iter()
+            .filter(|w| w.output == current.output)
+            .collect();
+        siblings.sort_by_key(|w| w.idx);
+        let Some(pos) = siblings.iter().position(|w| w.id == current.id) else {
+            return self;
+        };
+        let last = siblings.len() as i64 - 1;
+        let target = (pos as i64 + i64::from(offset)).clamp(0, last) as usize;
+        let target = WorkspaceReferenceArg::Id(siblings[target].id);
+        self.mv_win_wspace(Some(window.id), target, focus)
+    }
 
-    // Focus
-    pub fn monitor_l(self) -> Self {
-        self.send_action(json!({ "FocusMonitorLeft": {} }));
+    /// Moves every window on workspace `from` to workspace `to`, leaving focus
+    /// where it is. Windows that open while this runs stay put.
+    pub fn sweep_wspace(mut self, from: WorkspaceReferenceArg, to: WorkspaceReferenceArg) -> Self {
+        let Some(from) = self.resolve_wspace(&from) else {
+            return self;
+        };
+        let ids: Vec<u64> = self
+            .windows()
+            .expect("Failed to query windows")
+            .into_iter()
+            .filter(|w| w.workspace_id == Some(from))
+            .map(|w| w.id)
+            .collect();
+        for id in ids {
+            self = self.mv_win_wspace(Some(id), to.clone(), false);
+        }
         self
     }
-    pub fn monitor_r(self) -> Self {
-        self.send_action(json!({ "FocusMonitorRight": {} }));
-        self
+
+    // Monitors
+    /// Focuses the `idx`-th enabled output counting from the left, by the x
+    /// of its logical position (then by y, for outputs stacked vertically).
+    /// `idx` starts at 0. Does nothing if there are fewer outputs.
+    pub fn monitor_idx(mut self, idx: usize) -> Self {
+        let mut outputs: Vec<(i32, i32, String)> = self
+            .outputs()
+            .expect("Failed to query outputs")
+            .into_iter()
+            .filter_map(|o| o.logical.map(|l| (l.x, l.y, o.name)))
+            .collect();
+        outputs.sort();
+        match outputs.into_iter().nth(idx) {
+            Some((_, _, name)) => self.monitor_name(name),
+            None => self,
+        }
     }
-    pub fn monitor_u(self) -> Self {
-        self.send_action(json!({ "FocusMonitorUp": {} }));
-        self
+
+    /// Moves the focused column to `output`, then to column `index` there.
+    /// niri handles each action before replying, and the column keeps focus
+    /// as it moves, so the index applies on `output`. To be sure, the focused
+    /// output is checked in between, and the column is left where it landed
+    /// if it isn't `output`, e.g. when no output has that name.
+    pub fn mv_col_to(mut self, output: &str, index: usize) -> Self {
+        self = self.mv_col_mon(output.to_string());
+        if !self.commands.dry_run {
+            let focused = self
+                .focused_output()
+                .expect("Failed to query focused output");
+            if focused.is_none_or(|o| o.name != output) {
+                return self;
+            }
+        }
+        self.mv_col_idx(index)
     }
-    pub fn monitor_d(self) -> Self {
-        self.send_action(json!({ "FocusMonitorDown": {} }));
-        self
+
+    /// `monitor_l`, then moves the pointer to the middle of the newly
+    /// focused output. See `pointer_to` for what moving the pointer needs.
+    pub fn monitor_l_warp(self) -> Self {
+        self.monitor_l().pointer_to_focused_output()
     }
-    pub fn monitor_prev(self) -> Self {
-        self.send_action(json!({ "FocusMonitorPrevious": {} }));
-        self
+    pub fn monitor_r_warp(self) -> Self {
+        self.monitor_r().pointer_to_focused_output()
     }
-    pub fn monitor_next(self) -> Self {
-        self.send_action(json!({ "FocusMonitorNext": {} }));
-        self
+
+    fn pointer_to_focused_output(mut self) -> Self {
+        let output = self
+            .focused_output()
+            .expect("Failed to query focused output");
+        match output.and_then(|o| o.logical) {
+            Some(l) => self.pointer_to(
+                l.x as f64 + l.width as f64 / 2.0,
+                l.y as f64 + l.height as f64 / 2.0,
+            ),
+            None => self,
+        }
     }
-    pub fn monitor_name(self, out: String) -> Self {
-        self.send_action(json!({ "FocusMonitor": { "output": out } }));
+
+    /// Sets the scale of `output` through niri's output configuration request,
+    /// the same one `niri msg output <name> scale` uses. The change lasts until
+    /// niri reloads its config or restarts; the config file is left alone.
+    /// The current scale is in `outputs()`, under `logical`. Panics if niri
+    /// has no output named `output`.
+    pub fn set_output_scale(mut self, output: &str, scale: f64) -> Self {
+        let action = OutputAction::Scale {
+            scale: ScaleToSet::Specific(scale),
+        };
+        if self.commands.dry_run {
+            eprintln!("niri output {output} (dry run): {action:?}");
+            return self;
+        }
+        let request = Request::Output {
+            output: output.to_string(),
+            action,
+        };
+        match self.request(request).expect("Failed to configure output") {
+            Response::OutputConfigChanged(OutputConfigChanged::OutputWasMissing) => {
+                panic!("niri has no output named {output}")
+            }
+            Response::OutputConfigChanged(OutputConfigChanged::Applied) => {}
+            _ => panic!("{}", Error::UnexpectedResponse),
+        }
         self
     }
 
     // Power
-    pub fn monitors_off(self) -> Self {
+    //
+    // niri doesn't report whether monitors are powered, so the state is
+    // whatever this connection last set. It goes stale if anything else
+    // changes it, including niri waking the monitors on input.
+    pub fn monitors_off(mut self) -> Self {
         self.send_action(json!({ "PowerOffMonitors": {} }));
+        self.monitors_powered = Some(false);
         self
     }
-    pub fn monitors_on(self) -> Self {
+    pub fn monitors_on(mut self) -> Self {
         self.send_action(json!({ "PowerOnMonitors": {} }));
+        self.monitors_powered = Some(true);
         self
     }
 
-    // Move Window to Monitor
-    pub fn mv_win_mon(self, id: Option<u64>, out: String) -> Self {
-        self.send_action(json!({ "MoveWindowToMonitor": { "id": id, "output": out } }));
-        self
-    }
-    pub fn mv_win_mon_l(self) -> Self {
-        self.send_action(json!({ "MoveWindowToMonitorLeft": {} }));
-        self
-    }
-    pub fn mv_win_mon_r(self) -> Self {
-        self.send_action(json!({ "MoveWindowToMonitorRight": {} }));
-        self
-    }
-    pub fn mv_win_mon_u(self) -> Self {
-        self.send_action(json!({ "MoveWindowToMonitorUp": {} }));
-        self
-    }
-    pub fn mv_win_mon_d(self) -> Self {
-        self.send_action(json!({ "MoveWindowToMonitorDown": {} }));
-        self
-    }
-    pub fn mv_win_mon_prev(self) -> Self {
-        self.send_action(json!({ "MoveWindowToMonitorPrevious": {} }));
-        self
-    }
-    pub fn mv_win_mon_next(self) -> Self {
-        self.send_action(json!({ "MoveWindowToMonitorNext": {} }));
-        self
+    /// Whether monitors were last powered on or off through this connection.
+    /// Returns `Error::Unsupported` if it hasn't touched them yet.
+    pub fn monitors_powered(&mut self) -> Result<bool, Error> {
+        self.monitors_powered.ok_or(Error::Unsupported)
     }
 
-    // Move Column to Monitor
-    pub fn mv_col_mon(self, out: String) -> Self {
-        self.send_action(json!({ "MoveColumnToMonitor": { "output": out } }));
-        self
-    }
-    pub fn mv_col_mon_l(self) -> Self {
-        self.send_action(json!({ "MoveColumnToMonitorLeft": {} }));
-        self
-    }
-    pub fn mv_col_mon_r(self) -> Self {
-        self.send_action(json!({ "MoveColumnToMonitorRight": {} }));
-        self
-    }
-    pub fn mv_col_mon_u(self) -> Self {
-        self.send_action(json!({ "MoveColumnToMonitorUp": {} }));
-        self
-    }
-    pub fn mv_col_mon_d(self) -> Self {
-        self.send_action(json!({ "MoveColumnToMonitorDown": {} }));
-        self
-    }
-    pub fn mv_col_mon_prev(self) -> Self {
-        self.send_action(json!({ "MoveColumnToMonitorPrevious": {} }));
-        self
-    }
-    pub fn mv_col_mon_next(self) -> Self {
-        self.send_action(json!({ "MoveColumnToMonitorNext": {} }));
-        self
+    /// Powers monitors on or off, skipping the action if this connection
+    /// already left them in that state.
+    pub fn monitors_set(self, on: bool) -> Self {
+        match (self.monitors_powered, on) {
+            (Some(powered), _) if powered == on => self,
+            (_, true) => self.monitors_on(),
+            (_, false) => self.monitors_off(),
+        }
     }
 
-    // Move Workspace to Monitor
-    pub fn mv_wspace_mon(self, out: String, r: Option<WorkspaceReferenceArg>) -> Self {
-        self.send_action(json!({ "MoveWorkspaceToMonitor": { "output": out, "reference": r } }));
-        self
-    }
-    pub fn mv_wspace_mon_l(self) -> Self {
-        self.send_action(json!({ "MoveWorkspaceToMonitorLeft": {} }));
-        self
-    }
-    pub fn mv_wspace_mon_r(self) -> Self {
-        self.send_action(json!({ "MoveWorkspaceToMonitorRight": {} }));
-        self
+    // Shortcuts
+    /// Whether keyboard shortcuts are inhibited, as far as this connection
+    /// knows. niri doesn't report it, so this only counts the toggles sent
+    /// from here, starting from not inhibited. niri also applies the inhibit
+    /// to whichever window was focused, not globally.
+    pub fn shortcuts_inhibited(&mut self) -> Result<bool, Error> {
+        Ok(self.shortcuts_inhibited)
     }
-    pub fn mv_wspace_mon_u(self) -> Self {
-        self.send_action(json!({ "MoveWorkspaceToMonitorUp": {} }));
+
+    /// Toggles the shortcut inhibit only if `shortcuts_inhibited` differs
+    /// from `inhibited`.
+    pub fn set_shortcuts_inhibited(mut self, inhibited: bool) -> Self {
+        if self.shortcuts_inhibited != inhibited {
+            self = self.inhibit_shortcuts();
+        }
         self
     }
-    pub fn mv_wspace_mon_d(self) -> Self {
-        self.send_action(json!({ "MoveWorkspaceToMonitorDown": {} }));
-        self
+
+    // Screenshots
+    /// Captures the `width` x `height` rectangle at `x`, `y` on the focused
+    /// output, in logical pixels from its top-left corner.
+    ///
+    /// niri can't capture a fixed region, so this runs `grim`, which has to be
+    /// installed. Panics if the rectangle doesn't fit on the output.
+    pub fn snap_region(
+        mut self,
+        x: i32,
+        y: i32,
+        width: i32,
+        height: i32,
+        pointer: bool,
+        path: Option<String>,
+    ) -> Self {
+        let Some(output) = self
+            .focused_output()
+            .expect("Failed to query focused output")
+            .and_then(|o| o.logical)
+        else {
+            return self;
+        };
+        let fits = x >= 0
+            && y >= 0
+            && width > 0
+            && height > 0
+            && i64::from(x) + i64::from(width) <= i64::from(output.width)
+            && i64::from(y) + i64::from(height) <= i64::from(output.height);
+        assert!(
+            fits,
+            "Region {width}x{height}+{x}+{y} is outside the {}x{} output",
+            output.width, output.height
+        );
+        create_parent_dir(path.as_deref());
+        let geometry = format!("{},{} {width}x{height}", output.x + x, output.y + y);
+        let mut args = vec!["-g", geometry.as_str()];
+        if pointer {
+            args.push("-c");
+        }
+        args.extend(path.as_deref());
+        self.sh_fmt("grim", &args)
     }
-    pub fn mv_wspace_mon_prev(self) -> Self {
-        self.send_action(json!({ "MoveWorkspaceToMonitorPrevious": {} }));
-        self
+
+    // Screencasting
+    /// What the dynamic cast target was last set to through this connection.
+    /// niri doesn't report it, so changes made elsewhere aren't seen.
+    pub fn cast_target(&mut self) -> Result<Option<CastTarget>, Error> {
+        Ok(self.cast_target.clone())
     }
-    pub fn mv_wspace_mon_next(self) -> Self {
-        self.send_action(json!({ "MoveWorkspaceToMonitorNext": {} }));
+
+    // Overview
+    pub fn wait_overview_open(self, timeout: Option<Duration>) -> Self {
+        self.wait_overview(true, timeout)
+    }
+    pub fn wait_overview_closed(self, timeout: Option<Duration>) -> Self {
+        self.wait_overview(false, timeout)
+    }
+    fn wait_overview(mut self, open: bool, timeout: Option<Duration>) -> Self {
+        self.require_event_stream("wait for the overview");
+        let deadline = timeout.map(|t| Instant::now() + t);
+        if self
+            .overview_is_open()
+            .expect("Failed to query overview state")
+            == open
+        {
+            return self;
+        }
+        while let Some(event) = self.next_event_until(deadline) {
+            if let Event::OverviewOpenedOrClosed { is_open } = event
+                && is_open == open
+            {
+                break;
+            }
+        }
         self
     }
+}
 
-    // -------------------------------------------------------------------------
-    //  Screenshots & Screencasting
-    // -------------------------------------------------------------------------
+/// The id of the workspace `r` refers to. Indices count on the focused output.
+fn find_wspace(workspaces: &[Workspace], r: &WorkspaceReferenceArg) -> Option<u64> {
+    let focused_output = workspaces
+        .iter()
+        .find(|w| w.is_focused)
+        .and_then(|w| w.output.as_ref());
+    workspaces
+        .iter()
+        .find(|w| match r {
+            WorkspaceReferenceArg::Id(id) => w.id == *id,
+            WorkspaceReferenceArg::Index(idx) => {
+                w.idx == *idx && w.output.as_ref() == focused_output
+            }
+            WorkspaceReferenceArg::Name(name) => w.name.as_deref() == Some(name),
+        })
+        .map(|w| w.id)
+}
 
-    pub fn snap(self, pointer: bool, path: Option<String>) -> Self {
-        self.send_action(json!({ "Screenshot": { "show_pointer": pointer, "path": path } }));
-        self
-    }
-    pub fn snap_screen(self, disk: bool, pointer: bool, path: Option<String>) -> Self {
-        self.send_action(json!({
-            "ScreenshotScreen": { "write_to_disk": disk, "show_pointer": pointer, "path": path }
-        }));
-        self
-    }
-    pub fn snap_win(self, id: Option<u64>, disk: bool, path: Option<String>) -> Self {
-        self.send_action(json!({
-            "ScreenshotWindow": { "id": id, "write_to_disk": disk, "path": path }
-        }));
-        self
+/// A running `systemd-inhibit` process, killed when dropped.
+struct IdleInhibitor(Option<Child>);
+
+impl IdleInhibitor {
+    fn start() -> Self {
+        let child = Command::new("systemd-inhibit")
+            .args(["--what=idle", "--who=niriscript", "--why=Script requested"])
+            .args(["sleep", "infinity"])
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .spawn();
+        match child {
+            Ok(child) => IdleInhibitor(Some(child)),
+            Err(e) => {
+                eprintln!("Couldn't start systemd-inhibit, idle not inhibited: {e}");
+                IdleInhibitor(None)
+            }
+        }
     }
+}
 
-    pub fn cast_win(self, id: Option<u64>) -> Self {
-        self.send_action(json!({ "SetDynamicCastWindow": { "id": id } }));
-        self
+impl Drop for IdleInhibitor {
+    fn drop(&mut self) {
+        if let Some(child) = &mut self.0 {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
     }
-    pub fn cast_mon(self, out: Option<String>) -> Self {
-        self.send_action(json!({ "SetDynamicCastMonitor": { "output": out } }));
-        self
+}
+
+/// Where event lines come from: niri's socket, or a recording being replayed.
+enum EventSource {
+    Socket(UnixStream),
+    Replay(File),
+}
+
+impl EventSource {
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> std::io::Result<()> {
+        match self {
+            EventSource::Socket(stream) => stream.set_read_timeout(timeout),
+            EventSource::Replay(_) => Ok(()),
+        }
     }
-    pub fn cast_clear(self) -> Self {
-        self.send_action(json!({ "ClearDynamicCastTarget": {} }));
-        self
+
+    fn set_nonblocking(&self, nonblocking: bool) -> std::io::Result<()> {
+        match self {
+            EventSource::Socket(stream) => stream.set_nonblocking(nonblocking),
+            EventSource::Replay(_) => Ok(()),
+        }
     }
+}
 
-    // -------------------------------------------------------------------------
-    //  System / Misc / Debug
-    // -------------------------------------------------------------------------
+/// Borrows a `Niri` whose event socket had its read timeout or blocking
+/// mode changed, and on drop puts whichever socket is current by then back
+/// to plain blocking reads. Waits read through one so
+/// neither a panic nor a reconnect partway through leaves later reads
+/// timing out early.
+struct TimeoutGuard<'a>(&'a mut Niri);
 
-    pub fn inhibit_shortcuts(self) -> Self {
-        self.send_action(json!({ "ToggleKeyboardShortcutsInhibit": {} }));
-        self
+impl Deref for TimeoutGuard<'_> {
+    type Target = Niri;
+    fn deref(&self) -> &Niri {
+        self.0
     }
+}
 
-    pub fn transition(self, delay: Option<u16>) -> Self {
-        self.send_action(json!({ "DoScreenTransition": { "delay_ms": delay } }));
-        self
+impl DerefMut for TimeoutGuard<'_> {
+    fn deref_mut(&mut self) -> &mut Niri {
+        self.0
     }
+}
 
-    pub fn hotkeys(self) -> Self {
-        self.send_action(json!({ "ShowHotkeyOverlay": {} }));
-        self
+impl Drop for TimeoutGuard<'_> {
+    fn drop(&mut self) {
+        if let Some(reader) = &self.0.event_reader {
+            let _ = reader.get_ref().set_read_timeout(None);
+            let _ = reader.get_ref().set_nonblocking(false);
+        }
     }
+}
 
-    // Overview
-    pub fn overview_toggle(self) -> Self {
-        self.send_action(json!({ "ToggleOverview": {} }));
-        self
+impl Read for EventSource {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match self {
+            EventSource::Socket(stream) => stream.read(buf),
+            EventSource::Replay(file) => file.read(buf),
+        }
     }
-    pub fn overview_open(self) -> Self {
-        self.send_action(json!({ "OpenOverview": {} }));
-        self
+}
+
+/// Subscribes to niri's events on a new socket. Reads block until an event
+/// arrives; waits set a timeout of their own for as long as they last.
+fn open_event_stream(socket_path: &str, capacity: usize) -> Result<BufReader<EventSource>, Error> {
+    let mut stream = connect_socket(socket_path)?;
+    stream.write_all(b"\"EventStream\"\n")?;
+    Ok(BufReader::with_capacity(
+        capacity,
+        EventSource::Socket(stream),
+    ))
+}
+
+pub(crate) fn create_parent_dir(path: Option<&str>) {
+    let Some(parent) = path.and_then(|p| Path::new(p).parent()) else {
+        return;
+    };
+    if !parent.as_os_str().is_empty() {
+        std::fs::create_dir_all(parent)
+            .unwrap_or_else(|e| panic!("Failed to create {}: {e}", parent.display()));
     }
-    pub fn overview_close(self) -> Self {
-        self.send_action(json!({ "CloseOverview": {} }));
-        self
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AppDef;
+    use crate::test_util::window;
+
+    fn recording(name: &str, events: &[serde_json::Value]) -> std::path::PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("niriscript-{name}-{}.jsonl", std::process::id()));
+        let lines: Vec<String> = events.iter().map(|e| e.to_string() + "\n").collect();
+        std::fs::write(&path, lines.concat()).unwrap();
+        path
     }
 
-    // Debugging
-    pub fn dbg_tint(self) -> Self {
-        self.send_action(json!({ "ToggleDebugTint": {} }));
-        self
+    #[test]
+    fn replayed_spawn_matches_like_a_live_stream() {
+        let mut open = window(1, "foot", 1, Some((1, 1)), 800.0);
+        open.is_focused = true;
+        let new = window(2, "foot", 1, Some((2, 1)), 800.0);
+        let path = recording(
+            "spawn",
+            &[
+                json!({ "WindowsChanged": { "windows": [&open] } }),
+                json!({ "WindowOpenedOrChanged": { "window": &open } }),
+                json!({ "WindowOpenedOrChanged": { "window": &new } }),
+            ],
+        );
+        let mut niri = Niri::from_recording(&path);
+        assert!(niri.seen_windows.contains(&1));
+        assert_eq!(niri.focus_history.back(), Some(&1));
+
+        let foot = AppDef::new("foot", "foot");
+        assert_eq!(niri.spawn_result(&foot, None), SpawnOutcome::Matched(2));
+        assert_eq!(niri.spawn_result(&foot, None), SpawnOutcome::StreamClosed);
+        std::fs::remove_file(path).unwrap();
     }
-    pub fn dbg_opaque(self) -> Self {
-        self.send_action(json!({ "DebugToggleOpaqueRegions": {} }));
-        self
+
+    #[test]
+    fn spawn_takes_an_undersized_window_once_it_grows() {
+        let splash = window(1, "gimp", 1, None, 300.0);
+        let mut grown = window(1, "gimp", 1, None, 1200.0);
+        grown.layout.window_size.1 = 800;
+        let path = recording(
+            "resize",
+            &[
+                json!({ "WindowsChanged": { "windows": [] } }),
+                json!({ "WindowOpenedOrChanged": { "window": &splash } }),
+                json!({ "WindowLayoutsChanged": { "changes": [[1, &grown.layout]] } }),
+            ],
+        );
+        let mut niri = Niri::from_recording(&path);
+
+        let gimp = AppDef::new("gimp", "gimp").min_window_size(Some((800, 600)));
+        assert_eq!(niri.spawn_result(&gimp, None), SpawnOutcome::Matched(1));
+        std::fs::remove_file(path).unwrap();
     }
-    pub fn dbg_damage(self) -> Self {
-        self.send_action(json!({ "DebugToggleDamage": {} }));
-        self
+
+    #[test]
+    fn events_outlast_an_idle_gap_longer_than_the_read_timeout() {
+        let (ours, mut theirs) = UnixStream::pair().unwrap();
+        let commands = CommandHandle {
+            socket_path: String::new(),
+            dry_run: true,
+            action_timeout: None,
+            retries: 0,
+            retry_backoff: Duration::ZERO,
+        };
+        let reader = Some(BufReader::new(EventSource::Socket(ours)));
+        let mut niri = Niri::with_event_reader(commands, reader, false);
+        niri.read_timeout = Some(Duration::from_millis(50));
+
+        let writer = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(300));
+            let line = json!({ "WindowClosed": { "id": 7 } }).to_string() + "\n";
+            theirs.write_all(line.as_bytes()).unwrap();
+        });
+        assert!(niri.next_event_until(None).is_none());
+        assert!(matches!(
+            niri.events().next(),
+            Some(Event::WindowClosed { id: 7 })
+        ));
+        writer.join().unwrap();
     }
 }
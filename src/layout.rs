@@ -0,0 +1,40 @@
+use crate::{AppDef, Niri, NiriActions, Spawnable};
+
+/// A row of single-window columns, each with a width as a fraction of the output.
+#[derive(Default)]
+pub struct Layout {
+    columns: Vec<(AppDef, f64)>,
+}
+
+impl Layout {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn column(mut self, app: impl Spawnable, width: f64) -> Self {
+        let def = AppDef::new(app.cmd(), app.app_id())
+            .skip_windows(app.skip_windows())
+            .min_window_size(app.min_window_size());
+        self.columns.push((def, width));
+        self
+    }
+
+    /// Spawns every app left to right, then sets the width of each window that
+    /// opened. Apps whose window never shows up are skipped. Focus ends on the
+    /// first window that opened.
+    pub fn apply(self, mut niri: Niri) -> Niri {
+        let mut opened = Vec::new();
+        for (app, width) in &self.columns {
+            if let Some(id) = niri.spawn_result(app, None).window_id() {
+                opened.push((id, *width));
+            }
+        }
+        for &(id, width) in &opened {
+            niri = niri.win_width_prop(Some(id), width);
+        }
+        match opened.first() {
+            Some(&(id, _)) => niri.foc_id(id),
+            None => niri,
+        }
+    }
+}
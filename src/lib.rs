@@ -1,4 +1,53 @@
+mod action_log;
+mod actions;
+mod anchor;
+mod app;
+mod builder;
+mod cast;
+mod column;
+mod command;
+mod error;
+mod events;
 mod ipc;
+mod layout;
+mod middleware;
+#[cfg(feature = "mock")]
+mod mock;
+mod parse;
+mod rules;
+mod session;
+mod shell;
+mod snapshot;
+#[cfg(test)]
+mod test_util;
 
+pub use action_log::ActionStatus;
+pub use action_log::LoggedAction;
+pub use actions::NiriActions;
+pub use anchor::Anchor;
+pub use app::App;
+pub use app::AppDef;
+pub use app::SpawnOutcome;
+pub use app::Spawnable;
+pub use builder::NiriBuilder;
+pub use cast::CastTarget;
+pub use column::Column;
+pub use command::CommandHandle;
+pub use error::Error;
+pub use events::EventKind;
+pub use events::WindowEvent;
 pub use ipc::Niri;
-pub use ipc::App;
+pub use layout::Layout;
+pub use middleware::Logged;
+pub use middleware::Throttled;
+#[cfg(feature = "mock")]
+pub use mock::MockNiri;
+pub use parse::check_proportion;
+pub use parse::parse_size_change;
+pub use parse::parse_workspace_ref;
+pub use rules::Rule;
+pub use rules::RuleEngine;
+pub use session::Session;
+pub use session::SessionApp;
+pub use snapshot::LayoutSnapshot;
+pub use snapshot::SnapshotWindow;
@@ -0,0 +1,64 @@
+use crate::NiriActions;
+use std::time::{Duration, Instant};
+
+/// Prints every action to stderr before passing it on to `N`.
+pub struct Logged<N> {
+    inner: N,
+}
+
+impl<N: NiriActions> Logged<N> {
+    pub fn new(inner: N) -> Self {
+        Logged { inner }
+    }
+
+    pub fn get_mut(&mut self) -> &mut N {
+        &mut self.inner
+    }
+
+    pub fn into_inner(self) -> N {
+        self.inner
+    }
+}
+
+impl<N: NiriActions> NiriActions for Logged<N> {
+    fn send_action(&mut self, action: serde_json::Value) {
+        eprintln!("niri action: {action}");
+        self.inner.send_action(action);
+    }
+}
+
+/// Passes actions on to `N`, sleeping first if the previous one went out less
+/// than `gap` ago. Useful when niri is animating and drops rapid actions.
+pub struct Throttled<N> {
+    inner: N,
+    gap: Duration,
+    last: Option<Instant>,
+}
+
+impl<N: NiriActions> Throttled<N> {
+    pub fn new(inner: N, gap: Duration) -> Self {
+        Throttled {
+            inner,
+            gap,
+            last: None,
+        }
+    }
+
+    pub fn get_mut(&mut self) -> &mut N {
+        &mut self.inner
+    }
+
+    pub fn into_inner(self) -> N {
+        self.inner
+    }
+}
+
+impl<N: NiriActions> NiriActions for Throttled<N> {
+    fn send_action(&mut self, action: serde_json::Value) {
+        if let Some(last) = self.last {
+            std::thread::sleep(self.gap.saturating_sub(last.elapsed()));
+        }
+        self.inner.send_action(action);
+        self.last = Some(Instant::now());
+    }
+}
@@ -0,0 +1,25 @@
+use crate::NiriActions;
+
+/// Stands in for `Niri` in tests: every action is recorded instead of sent,
+/// so a script's output can be asserted on without niri running.
+#[derive(Debug, Default)]
+pub struct MockNiri {
+    actions: Vec<serde_json::Value>,
+}
+
+impl MockNiri {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Every action issued so far, oldest first, in niri's JSON form.
+    pub fn actions(&self) -> &[serde_json::Value] {
+        &self.actions
+    }
+}
+
+impl NiriActions for MockNiri {
+    fn send_action(&mut self, action: serde_json::Value) {
+        self.actions.push(action);
+    }
+}
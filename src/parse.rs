@@ -0,0 +1,115 @@
+use crate::Error;
+use niri_ipc::{SizeChange, WorkspaceReferenceArg};
+
+/// Parses a size change the way `niri msg` does: `800` sets a fixed size,
+/// `+100`/`-100` adjust it, and a trailing `%` (`50%`, `+10%`) makes either
+/// form a proportion.
+pub fn parse_size_change(s: &str) -> Result<SizeChange, Error> {
+    s.trim()
+        .parse()
+        .map_err(|e: &str| Error::InvalidArgument(format!("invalid size change {s:?}: {e}")))
+}
+
+/// Reads a workspace from one argument: `#7` is the workspace with id 7, a
+/// number that fits an index (`0`-`255`) is an index on the focused output,
+/// and anything else, including a malformed `#` form, is a name.
+pub fn parse_workspace_ref(s: &str) -> WorkspaceReferenceArg {
+    if let Some(id) = s.strip_prefix('#').and_then(|id| id.parse().ok()) {
+        WorkspaceReferenceArg::Id(id)
+    } else if let Ok(idx) = s.parse() {
+        WorkspaceReferenceArg::Index(idx)
+    } else {
+        WorkspaceReferenceArg::Name(s.to_string())
+    }
+}
+
+/// Smallest fraction an out-of-range value is clamped up to.
+const MIN_PROPORTION: f64 = 0.05;
+
+/// Checks that `val` is a fraction of the output in `(0.0, 1.0]`, as
+/// `col_width`, `win_width_prop` and `win_height` expect.
+pub fn check_proportion(val: f64) -> Result<f64, Error> {
+    if val > 0.0 && val <= 1.0 {
+        Ok(val)
+    } else {
+        Err(Error::InvalidArgument(format!(
+            "proportion {val} is outside (0.0, 1.0]"
+        )))
+    }
+}
+
+/// Clamps `val` into `(0.0, 1.0]`, warning on stderr if it wasn't already.
+fn clamp_proportion(val: f64) -> f64 {
+    check_proportion(val).unwrap_or_else(|e| {
+        let clamped = if val > 1.0 { 1.0 } else { MIN_PROPORTION };
+        eprintln!("niriscript: {e}; using {clamped}");
+        clamped
+    })
+}
+
+/// The change that sets a size to `val`, a fraction of the output clamped as
+/// by `clamp_proportion`. niri takes proportions in percent, so `0.5` is sent
+/// as `SetProportion(50.0)`.
+pub(crate) fn set_proportion(val: f64) -> SizeChange {
+    SizeChange::SetProportion(clamp_proportion(val) * 100.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(s: &str) -> SizeChange {
+        parse_size_change(s).unwrap()
+    }
+
+    #[test]
+    fn fixed_sizes() {
+        assert_eq!(parse("800"), SizeChange::SetFixed(800));
+        assert_eq!(parse("+100"), SizeChange::AdjustFixed(100));
+        assert_eq!(parse("-100"), SizeChange::AdjustFixed(-100));
+    }
+
+    #[test]
+    fn proportions() {
+        assert_eq!(parse("50%"), SizeChange::SetProportion(50.0));
+        assert_eq!(parse("+10%"), SizeChange::AdjustProportion(10.0));
+        assert_eq!(parse("-10%"), SizeChange::AdjustProportion(-10.0));
+    }
+
+    #[test]
+    fn fractions_are_sent_in_percent() {
+        assert_eq!(
+            serde_json::to_value(set_proportion(0.5)).unwrap(),
+            serde_json::json!({ "SetProportion": 50.0 })
+        );
+        assert_eq!(set_proportion(1.0), SizeChange::SetProportion(100.0));
+        assert_eq!(set_proportion(50.0), SizeChange::SetProportion(100.0));
+        assert_eq!(set_proportion(0.0), SizeChange::SetProportion(5.0));
+    }
+
+    #[test]
+    fn surrounding_whitespace_is_ignored() {
+        assert_eq!(parse("  800\n"), SizeChange::SetFixed(800));
+        assert_eq!(parse(" +10% "), SizeChange::AdjustProportion(10.0));
+    }
+
+    #[test]
+    fn workspace_refs() {
+        use WorkspaceReferenceArg::*;
+        assert_eq!(parse_workspace_ref("#42"), Id(42));
+        assert_eq!(parse_workspace_ref("3"), Index(3));
+        assert_eq!(parse_workspace_ref("mail"), Name("mail".to_string()));
+        assert_eq!(parse_workspace_ref("#mail"), Name("#mail".to_string()));
+        assert_eq!(parse_workspace_ref("300"), Name("300".to_string()));
+    }
+
+    #[test]
+    fn rejects_malformed_sizes() {
+        for s in ["", "   ", "%", "800px", "50%x", "+", "abc"] {
+            assert!(
+                matches!(parse_size_change(s), Err(Error::InvalidArgument(_))),
+                "{s:?} should not parse"
+            );
+        }
+    }
+}
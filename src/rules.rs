@@ -0,0 +1,151 @@
+use crate::app::read_config;
+use crate::{Error, Niri, NiriActions};
+use niri_ipc::{Event, Window};
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::path::Path;
+
+/// What to do with windows matching `app_id` and `title`. Unset matchers
+/// match any window.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Rule {
+    pub app_id: Option<String>,
+    /// Substring the window title has to contain.
+    pub title: Option<String>,
+    pub floating: Option<bool>,
+    /// Width as a fraction of the output, as passed to `win_width_prop`.
+    pub width: Option<f64>,
+    /// Name of the workspace to move the window to.
+    pub workspace: Option<String>,
+}
+
+impl Rule {
+    fn matches(&self, window: &Window) -> bool {
+        let field = |want: &Option<String>, have: &Option<String>, exact: bool| match want {
+            None => true,
+            Some(want) => have.as_deref().is_some_and(|have| {
+                if exact {
+                    have == want
+                } else {
+                    have.contains(want.as_str())
+                }
+            }),
+        };
+        field(&self.app_id, &window.app_id, true) && field(&self.title, &window.title, false)
+    }
+}
+
+/// Applies `[[rule]]` entries to windows as they open or change, on top of
+/// niri's own window rules. Each rule fires at most once per window, on the
+/// first event where the window matches it.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct RuleEngine {
+    #[serde(rename = "rule", default)]
+    pub rules: Vec<Rule>,
+    #[serde(skip)]
+    fired: HashSet<(u64, usize)>,
+}
+
+impl RuleEngine {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn rule(mut self, rule: Rule) -> Self {
+        self.rules.push(rule);
+        self
+    }
+
+    pub fn load_from(path: impl AsRef<Path>) -> Result<RuleEngine, Error> {
+        read_config(path.as_ref())
+    }
+
+    /// Applies the rules to windows opened from now on, until the event
+    /// stream ends. Windows that are already open are left alone.
+    pub fn run(mut self, mut niri: Niri) -> Niri {
+        let open = niri.windows().expect("Failed to query windows");
+        for window in &open {
+            self.fired
+                .extend((0..self.rules.len()).map(|i| (window.id, i)));
+        }
+        while let Some(event) = niri.next_event() {
+            match event {
+                Event::WindowOpenedOrChanged { window } => niri = self.apply(niri, &window),
+                Event::WindowClosed { id } => self.fired.retain(|(window, _)| *window != id),
+                _ => {}
+            }
+        }
+        niri
+    }
+
+    fn apply(&mut self, mut niri: Niri, window: &Window) -> Niri {
+        let id = Some(window.id);
+        for (i, rule) in self.rules.iter().enumerate() {
+            if !rule.matches(window) || !self.fired.insert((window.id, i)) {
+                continue;
+            }
+            match rule.floating {
+                Some(true) if !window.is_floating => niri = niri.mv_float(id),
+                Some(false) if window.is_floating => niri = niri.mv_tile(id),
+                _ => {}
+            }
+            if let Some(width) = rule.width {
+                niri = niri.win_width_prop(id, width);
+            }
+            if let Some(workspace) = &rule.workspace {
+                niri = niri.mv_win_wspace_name(id, workspace, false);
+            }
+        }
+        niri
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::window;
+
+    fn titled(app_id: &str, title: Option<&str>) -> Window {
+        let mut w = window(1, app_id, 1, Some((1, 1)), 800.0);
+        w.title = title.map(str::to_string);
+        w
+    }
+
+    fn rule(app_id: Option<&str>, title: Option<&str>) -> Rule {
+        Rule {
+            app_id: app_id.map(str::to_string),
+            title: title.map(str::to_string),
+            ..Rule::default()
+        }
+    }
+
+    #[test]
+    fn app_id_matches_exactly_and_title_by_substring() {
+        let w = titled("foot", Some("vim notes.md"));
+        assert!(rule(None, None).matches(&w));
+        assert!(rule(Some("foot"), Some("notes")).matches(&w));
+        assert!(!rule(Some("foo"), None).matches(&w));
+        assert!(!rule(None, Some("emacs")).matches(&w));
+        assert!(!rule(None, Some("notes")).matches(&titled("foot", None)));
+    }
+
+    #[test]
+    fn each_rule_fires_once_per_window() {
+        let path =
+            std::env::temp_dir().join(format!("niriscript-rules-{}.jsonl", std::process::id()));
+        std::fs::write(&path, "").unwrap();
+        let mut niri = Niri::from_recording(&path);
+        let mut engine = RuleEngine::new()
+            .rule(Rule {
+                floating: Some(true),
+                ..rule(Some("foot"), None)
+            })
+            .rule(rule(Some("mpv"), None));
+
+        let w = titled("foot", None);
+        niri = engine.apply(niri, &w);
+        engine.apply(niri, &w);
+        assert_eq!(engine.fired, HashSet::from([(1, 0)]));
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,53 @@
+use crate::app::read_config;
+use crate::{Error, Niri, NiriActions, Spawnable};
+use serde::Deserialize;
+use std::path::Path;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SessionApp {
+    pub cmd: String,
+    pub id: String,
+    /// Name of the workspace to move the window to.
+    pub workspace: Option<String>,
+    /// Width as a fraction of the output, as passed to `win_width_prop`.
+    pub width: Option<f64>,
+}
+
+impl Spawnable for SessionApp {
+    fn cmd(&self) -> &str {
+        &self.cmd
+    }
+    fn app_id(&self) -> &str {
+        &self.id
+    }
+}
+
+/// A set of apps to launch and place, read from `[[app]]` entries.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Session {
+    #[serde(rename = "app", default)]
+    pub apps: Vec<SessionApp>,
+}
+
+impl Session {
+    pub fn load_from(path: impl AsRef<Path>) -> Result<Session, Error> {
+        read_config(path.as_ref())
+    }
+
+    /// Spawns the apps in order, waiting for each window before sizing it and
+    /// moving it to its workspace. Apps whose window never shows up are skipped.
+    pub fn launch(self, mut niri: Niri) -> Niri {
+        for app in &self.apps {
+            let Some(id) = niri.spawn_result(app, None).window_id() else {
+                continue;
+            };
+            if let Some(width) = app.width {
+                niri = niri.win_width_prop(Some(id), width);
+            }
+            if let Some(workspace) = &app.workspace {
+                niri = niri.mv_win_wspace_name(Some(id), workspace, false);
+            }
+        }
+        niri
+    }
+}
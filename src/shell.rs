@@ -0,0 +1,34 @@
+/// Quotes `s` as a single POSIX shell word, so it reaches the program as-is
+/// however many spaces, quotes or `$` it contains.
+pub(crate) fn quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::quote;
+    use std::process::Command;
+
+    fn round_trip(s: &str) -> String {
+        let out = Command::new("sh")
+            .arg("-c")
+            .arg(format!("printf %s {}", quote(s)))
+            .output()
+            .unwrap();
+        String::from_utf8(out.stdout).unwrap()
+    }
+
+    #[test]
+    fn words_reach_the_program_as_is() {
+        for s in [
+            "it's",
+            "two words",
+            "$(echo hi) `echo hi`",
+            "a; echo b",
+            "line\nbreak",
+            "",
+        ] {
+            assert_eq!(round_trip(s), s);
+        }
+    }
+}
@@ -0,0 +1,110 @@
+use niri_ipc::{Window, Workspace};
+use serde::{Deserialize, Serialize};
+
+/// Where windows were and how big, as captured by `Niri::snapshot` for
+/// `Niri::restore`. Serializable, so it can be kept across runs.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LayoutSnapshot {
+    pub windows: Vec<SnapshotWindow>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotWindow {
+    pub app_id: Option<String>,
+    pub title: Option<String>,
+    pub workspace_id: Option<u64>,
+    pub workspace_name: Option<String>,
+    pub is_floating: bool,
+    /// Window size in logical pixels.
+    pub size: (i32, i32),
+}
+
+impl LayoutSnapshot {
+    pub(crate) fn capture(windows: &[Window], workspaces: &[Workspace]) -> Self {
+        let windows = windows
+            .iter()
+            .map(|w| SnapshotWindow {
+                app_id: w.app_id.clone(),
+                title: w.title.clone(),
+                workspace_id: w.workspace_id,
+                workspace_name: workspaces
+                    .iter()
+                    .find(|ws| Some(ws.id) == w.workspace_id)
+                    .and_then(|ws| ws.name.clone()),
+                is_floating: w.is_floating,
+                size: w.layout.window_size,
+            })
+            .collect();
+        LayoutSnapshot { windows }
+    }
+
+    /// Pairs each snapshot window with a current window: first those with the
+    /// same app_id and title, then the rest by app_id alone. Each current
+    /// window is used at most once; snapshot windows left over are dropped.
+    pub(crate) fn match_windows(&self, current: &[Window]) -> Vec<(&SnapshotWindow, u64)> {
+        let mut taken = vec![false; current.len()];
+        let mut matched: Vec<Option<u64>> = vec![None; self.windows.len()];
+        for exact in [true, false] {
+            for (snap, slot) in self.windows.iter().zip(matched.iter_mut()) {
+                if slot.is_some() || snap.app_id.is_none() {
+                    continue;
+                }
+                let found = (0..current.len()).find(|&i| {
+                    let w = &current[i];
+                    !taken[i] && w.app_id == snap.app_id && (!exact || w.title == snap.title)
+                });
+                if let Some(i) = found {
+                    taken[i] = true;
+                    *slot = Some(current[i].id);
+                }
+            }
+        }
+        self.windows
+            .iter()
+            .zip(matched)
+            .filter_map(|(snap, id)| Some((snap, id?)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::window;
+
+    fn titled(id: u64, app_id: &str, title: &str) -> Window {
+        let mut w = window(id, app_id, 1, Some((id as usize, 1)), 800.0);
+        w.title = Some(title.to_string());
+        w
+    }
+
+    #[test]
+    fn exact_titles_win_before_app_id_fallback() {
+        let current = [
+            titled(1, "foot", "b"),
+            titled(2, "foot", "c"),
+            titled(3, "foot", "a"),
+        ];
+        let mut snapshot = LayoutSnapshot::capture(
+            &[
+                titled(10, "foot", "x"),
+                titled(11, "foot", "a"),
+                titled(12, "foot", "b"),
+                titled(13, "foot", "y"),
+                titled(14, "mpv", "a"),
+            ],
+            &[],
+        );
+        snapshot.windows.push(SnapshotWindow {
+            app_id: None,
+            ..snapshot.windows[0].clone()
+        });
+
+        let matched: Vec<(Option<&str>, u64)> = snapshot
+            .match_windows(&current)
+            .into_iter()
+            .map(|(snap, id)| (snap.title.as_deref(), id))
+            .collect();
+        assert_eq!(matched, [(Some("x"), 2), (Some("a"), 3), (Some("b"), 1)]);
+    }
+}
@@ -0,0 +1,28 @@
+use niri_ipc::Window;
+use serde_json::json;
+
+/// An unfocused window as niri reports it: tiled at `pos` on `workspace`, or
+/// floating without one, `width` wide and 500 high.
+pub(crate) fn window(
+    id: u64,
+    app_id: &str,
+    workspace: u64,
+    pos: Option<(usize, usize)>,
+    width: f64,
+) -> Window {
+    serde_json::from_value(json!({
+        "id": id,
+        "app_id": app_id,
+        "workspace_id": workspace,
+        "is_focused": false,
+        "is_floating": pos.is_none(),
+        "is_urgent": false,
+        "layout": {
+            "pos_in_scrolling_layout": pos,
+            "tile_size": [width, 500.0],
+            "window_size": [width as i32, 500],
+            "window_offset_in_tile": [0.0, 0.0],
+        },
+    }))
+    .unwrap()
+}